@@ -1,9 +1,9 @@
 import { Component, OnInit } from '@angular/core'
-import { DataTable, getSourcePipes, PipeConfig, runDataPipeline } from 'polars-pipes'
+import { DataTable, DataTableColumnType, getSourcePipes, PipeConfig, runDataPipeline } from 'polars-pipes'
 
-export function toDataTypeArrays(inputData: any[], columnSchema: {[k: string]: keyof DataTable}): DataTable {
+export function toDataTypeArrays(inputData: any[], columnSchema: {[k: string]: DataTableColumnType}): DataTable {
     // TODO: Handle case when some records are missing keys...
-    const initialMap: DataTable = { f64: new Map(), i64: new Map(), str: new Map(), datetime: new Map(), bool: new Map() }
+    const initialMap: DataTable = { f64: new Map(), i64: new Map(), str: new Map(), datetime: new Map(), bool: new Map(), list_f64: new Map(), list_i64: new Map(), list_str: new Map(), list_bool: new Map(), columnOrder: Object.keys(columnSchema) }
     Object.entries(columnSchema).forEach(([k,v]) => {
         initialMap[v]!.set(k, [])
     })
@@ -18,13 +18,15 @@ export function fromDataTypeArrays(input: DataTable): any[] {
     const columnIters: { name: string, iterRef: any[] }[] = []
     let maxRows = 0
     Object.entries(input)
-        .filter(([k,v]) => v !== undefined)
+        .filter(([k,v]) => v !== undefined && k !== 'columnOrder' && k !== 'castFailures')
         .forEach(([k,v]) => {
             for (let [k, v2] of v) {
                 columnIters.push({ name: k, iterRef: v2 })
                 maxRows = Math.max(maxRows, v2.length)
             }
         })
+    const columnOrder = input.columnOrder ?? []
+    columnIters.sort((a, b) => columnOrder.indexOf(a.name) - columnOrder.indexOf(b.name))
     const records: any[] = []
     for (let i = 0; i < maxRows; i++) {
         records.push(columnIters.reduce((acc, { name, iterRef }) => {
@@ -167,7 +169,7 @@ export class MainComponent implements OnInit {
             studentScores: toDataTypeArrays(this.studentScores, { name: 'str', subject: 'str', score: 'f64' }),
             subjectMultipliers: toDataTypeArrays(this.subjectMultipliers, { subject: 'str', semester: 'i64', multiplier: 'f64' }),
         }
-        const result = runDataPipeline(['overPipe'], inputData, pipeConfigs)
+        const result = runDataPipeline(['overPipe'], inputData, pipeConfigs).get('overPipe')
         // const result = polarsPipes.run_data_pipeline(['adjustedScoresDerivedValues'], inputData, pipeConfigs)
         // const result = polarsPipes.run_data_pipeline(['adjustedScores'], inputData, pipeConfigs)
         console.log('RESULT IS', fromDataTypeArrays(result))
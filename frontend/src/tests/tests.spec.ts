@@ -1,9 +1,8 @@
-import { PipeConfig } from "polars-pipes/polars_pipes.original"
-import { DataTable } from "polars-pipes"
+import { DataTable, DataTableColumnType, PipeConfig } from "polars-pipes"
 
-export function toDataTypeArrays(inputData: any[], columnSchema: {[k: string]: keyof DataTable}): DataTable {
+export function toDataTypeArrays(inputData: any[], columnSchema: {[k: string]: DataTableColumnType}): DataTable {
     // TODO: Handle case when some records are missing keys...
-    const initialMap: DataTable = { f64: new Map(), i64: new Map(), str: new Map(), datetime: new Map(), bool: new Map() }
+    const initialMap: DataTable = { f64: new Map(), i64: new Map(), str: new Map(), datetime: new Map(), bool: new Map(), list_f64: new Map(), list_i64: new Map(), list_str: new Map(), list_bool: new Map(), columnOrder: Object.keys(columnSchema) }
     Object.entries(columnSchema).forEach(([k,v]) => {
         initialMap[v]!.set(k, [])
     })
@@ -18,13 +17,15 @@ export function fromDataTypeArrays(input: DataTable): any[] {
     const columnIters: { name: string, iterRef: any[] }[] = []
     let maxRows = 0
     Object.entries(input)
-        .filter(([k,v]) => v !== undefined)
+        .filter(([k,v]) => v !== undefined && k !== 'columnOrder' && k !== 'castFailures')
         .forEach(([k,v]) => {
             for (let [k, v2] of v) {
                 columnIters.push({ name: k, iterRef: v2 })
                 maxRows = Math.max(maxRows, v2.length)
             }
         })
+    const columnOrder = input.columnOrder ?? []
+    columnIters.sort((a, b) => columnOrder.indexOf(a.name) - columnOrder.indexOf(b.name))
     const records: any[] = []
     for (let i = 0; i < maxRows; i++) {
         records.push(columnIters.reduce((acc, { name, iterRef }) => {
@@ -36,7 +37,7 @@ export function fromDataTypeArrays(input: DataTable): any[] {
 }
 
 
-type TableSchema = {[j: string]: keyof DataTable}
+type TableSchema = {[j: string]: DataTableColumnType}
 
 const INPUT_DATA: {[k: string]: [TableSchema, any[]]} = {
     sourceId1: [
@@ -58,6 +59,66 @@ const INPUT_DATA: {[k: string]: [TableSchema, any[]]} = {
             { year: 2022, taxRate: 0.25 },
         ],
     ],
+    sourceId3: [
+        { code: 'str', flag: 'str' },
+        [
+            { code: '1', flag: 'true' },
+            { code: 'x', flag: 'False' },
+            { code: '3', flag: 'maybe' },
+        ],
+    ],
+    sourceId4: [
+        { name: 'str', tags: 'list_str' },
+        [
+            { name: 'a', tags: ['x', 'y'] },
+            { name: 'b', tags: [] },
+            { name: 'c', tags: ['z'] },
+        ],
+    ],
+    sourceId5: [
+        { period: 'str', start: 'i64', end: 'i64' },
+        [
+            { period: 'early', start: 1, end: 2 },
+            { period: 'late', start: 3, end: 3 },
+        ],
+    ],
+    sourceId6: [
+        { day: 'str', at: 'str' },
+        [
+            { day: '2021-01-02', at: '2021-01-02 03:04' },
+            { day: 'bad', at: 'bad' },
+        ],
+    ],
+    sourceId7: [
+        { team: 'str', at: 'datetime', reading: 'i64' },
+        [
+            { team: 'a', at: 1000, reading: 1 },
+            { team: 'b', at: 2800, reading: 2 },
+            { team: 'a', at: 3000, reading: 3 },
+        ],
+    ],
+    sourceId8: [
+        { team: 'str', at: 'datetime', status: 'str' },
+        [
+            { team: 'a', at: 500, status: 'a0' },
+            { team: 'b', at: 1500, status: 'b0' },
+            { team: 'a', at: 2500, status: 'a1' },
+        ],
+    ],
+    sourceId9: [
+        { period: 'str', start: 'i64', end: 'i64' },
+        [
+            { period: 'year', start: 1, end: 12 },
+            { period: 'spring', start: 3, end: 5 },
+        ],
+    ],
+    sourceId10: [
+        { period: 'str', start: 'i64', end: 'i64' },
+        [
+            { period: 'first', start: 1, end: 2 },
+            { period: 'second', start: 2, end: 3 },
+        ],
+    ],
 }
 
 const INPUT_DATA_TABLES: [string, DataTable][] = Object.entries(INPUT_DATA).map(([key, [schema, data]]) => {
@@ -102,6 +163,26 @@ const PIPE_CONFIGS: {[k: string]: PipeConfig} = {
         on: ['year'],
         how: 'Outer',
     },
+    joinSemi1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'Semi' },
+    joinAnti1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'Anti' },
+    joinCross1: { type: 'Join', leftPipeId: 'source2', rightPipeId: 'source3', how: 'Cross' },
+    joinCrossLimited1: { type: 'Join', leftPipeId: 'source2', rightPipeId: 'source3', how: 'Cross', maxRows: 5 },
+    joinCrossKeys1: { type: 'Join', leftPipeId: 'source2', rightPipeId: 'source3', how: 'Cross', leftOn: ['year'], rightOn: ['code'] },
+    renameTaxYear1: { type: 'Rename', pipeId: 'source2', properties: [{ from: 'year', to: 'taxYear' }] },
+    joinLeftOnRightOn1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'renameTaxYear1', leftOn: ['year'], rightOn: ['taxYear'], how: 'Inner' },
+    joinSuffix1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'headPipe1', on: ['year', 'month'], how: 'Inner', suffix: '_head' },
+    joinManyToOne1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'Left', validate: 'ManyToOne' },
+    joinOneToOne1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'Left', validate: 'OneToOne' },
+    joinAsOf1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'AsOf' },
+    joinAsOfForward1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'AsOf', strategy: 'Forward' },
+    joinAsOfTolerance1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'AsOf', tolerance: 0 },
+    source7: { type: 'Source', sourceId: 'sourceId7' },
+    source8: { type: 'Source', sourceId: 'sourceId8' },
+    joinAsOfDatetime1: { type: 'Join', leftPipeId: 'source7', rightPipeId: 'source8', on: ['at'], how: 'AsOf', tolerance: 400 },
+    joinAsOfFractionalTolerance1: { type: 'Join', leftPipeId: 'source7', rightPipeId: 'source8', on: ['at'], how: 'AsOf', tolerance: 0.5 },
+    joinAsOfBy1: { type: 'Join', leftPipeId: 'source7', rightPipeId: 'source8', on: ['at'], how: 'AsOf', by: ['team'] },
+    joinAsOfValidated1: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'source2', on: ['year'], how: 'AsOf', validate: 'ManyToOne' },
+    joinCrossValidated1: { type: 'Join', leftPipeId: 'source2', rightPipeId: 'source3', how: 'Cross', validate: 'OneToOne' },
     addPipe1: {
         type: 'DerivedValues',
         pipeId: 'source1',
@@ -123,15 +204,86 @@ const PIPE_CONFIGS: {[k: string]: PipeConfig} = {
                 operands: [{ property: 'revenue' }, { property: 'cost' }, -1]
             }
         }]
-    }
+    },
+    sortPipe1: {
+        type: 'Sort',
+        pipeId: 'source1',
+        by: [{ property: 'year', descending: true }, { property: 'month' }],
+    },
+    headPipe1: { type: 'Slice', pipeId: 'source1', offset: 0, length: 2 },
+    tailPipe1: { type: 'Slice', pipeId: 'source1', offset: -2 },
+    selectPipe1: { type: 'Select', pipeId: 'source1', properties: ['cost', 'year'] },
+    dropPipe1: { type: 'Drop', pipeId: 'source1', properties: ['*e*'], matching: 'Glob' },
+    selectMissingPipe1: { type: 'Select', pipeId: 'source1', properties: ['year', 'profit', 'tax'] },
+    unionByNamePipe1: { type: 'Union', pipeIds: ['headPipe1', 'source2'], mode: 'ByName', sourceColumn: 'from' },
+    unionSourceClashPipe1: { type: 'Union', pipeIds: ['source1', 'renameTaxYear1'], mode: 'ByName', sourceColumn: 'taxYear' },
+    unionStrictPipe1: { type: 'Union', pipeIds: ['headPipe1', 'source2'], mode: 'Strict' },
+    pivotPipe1: { type: 'Pivot', pipeId: 'source1', index: ['year'], columns: 'month', values: 'revenue', agg: 'Sum', pivotValues: ['1', '2', '3'] },
+    pivotIndexClashPipe1: { type: 'Pivot', pipeId: 'source1', index: ['year'], columns: 'month', values: 'revenue', agg: 'Sum', pivotValues: ['1', 'year'] },
+    pivotDataPipe1: { type: 'Pivot', pipeId: 'source1', index: ['year'], columns: 'month', values: 'revenue', agg: 'Sum' },
+    monthAsFloat1: { type: 'Cast', pipeId: 'source1', columns: [{ property: 'month', to: 'f64' }] },
+    pivotFloatPipe1: { type: 'Pivot', pipeId: 'monthAsFloat1', index: ['year'], columns: 'month', values: 'revenue', agg: 'Sum', pivotValues: ['1', '2'] },
+    unpivotPipe1: {
+        type: 'Unpivot',
+        pipeId: 'headPipe1',
+        idColumns: ['year', 'month'],
+        valueColumns: ['revenue', 'cost'],
+        variableName: 'measure',
+        valueName: 'amount',
+    },
+    unpivotGlobPipe1: { type: 'Unpivot', pipeId: 'headPipe1', idColumns: ['year', 'month'], valueColumns: ['*e*'], matching: 'Glob' },
+    unpivotOnlyIdsPipe1: { type: 'Unpivot', pipeId: 'headPipe1', idColumns: ['year', 'month'], valueColumns: ['^(year|month)$'], matching: 'Regex' },
+    distinctLastPipe1: { type: 'Distinct', pipeId: 'source1', subset: ['year'], keep: 'Last', maintainOrder: true },
+    source3: { type: 'Source', sourceId: 'sourceId3' },
+    castPipe1: { type: 'Cast', pipeId: 'source3', columns: [{ property: 'code', to: 'i64' }, { property: 'flag', to: 'bool' }] },
+    source6: { type: 'Source', sourceId: 'sourceId6' },
+    castDatesPipe1: {
+        type: 'Cast',
+        pipeId: 'source6',
+        columns: [{ property: 'day', to: 'date', format: '%Y-%m-%d' }, { property: 'at', to: 'datetime', format: '%Y-%m-%d %H:%M' }],
+    },
+    formatDatePipe1: { type: 'Cast', pipeId: 'castDatesPipe1', columns: [{ property: 'day', to: 'str', format: '%d/%m/%Y' }] },
+    source6Head: { type: 'Slice', pipeId: 'source6', offset: 0, length: 1 },
+    stringToDatePipe1: { type: 'StringToDate', pipeId: 'source6Head', columnFrom: 'day', columnTo: 'date', format: '%Y-%m-%d' },
+    castStrictPipe1: { type: 'Cast', pipeId: 'source3', columns: [{ property: 'code', to: 'i64' }], strict: true },
+    castCountPipe1: { type: 'Cast', pipeId: 'source3', columns: [{ property: 'code', to: 'i64' }, { property: 'flag', to: 'bool' }], countFailures: true },
+    castCountSelectPipe1: { type: 'Select', pipeId: 'castCountPipe1', properties: ['code'] },
+    fillNullLiteralPipe1: { type: 'FillNull', pipeId: 'joinLeft1', rules: [{ property: 'taxRate', strategy: 'Literal', value: 0.5 }] },
+    fillNullForwardPipe1: { type: 'FillNull', pipeId: 'joinLeft1', rules: [{ property: 'taxRate', strategy: 'Forward' }] },
+    fillNullForwardOverPipe1: { type: 'FillNull', pipeId: 'joinLeft1', rules: [{ property: 'taxRate', strategy: 'Forward', over: ['year'] }] },
+    samplePipe1: { type: 'Sample', pipeId: 'source1', n: 3, seed: 42 },
+    source4: { type: 'Source', sourceId: 'sourceId4' },
+    explodePipe1: { type: 'Explode', pipeId: 'source4', properties: ['tags'] },
+    listAggPipe1: { type: 'GroupAndReduce', pipeId: 'source1', groupBy: ['year'], aggs: [{ name: 'months', type: 'List', aggProperty: 'month' }] },
+    listAggSortPipe1: { type: 'Sort', pipeId: 'listAggPipe1', by: [{ property: 'year' }] },
+    topNPipe1: { type: 'TopNPerGroup', pipeId: 'source1', groupBy: ['year'], orderBy: [{ property: 'revenue', descending: true }], n: 1 },
+    topNFirstPipe1: { type: 'TopNPerGroup', pipeId: 'joinLeft1', groupBy: ['year'], orderBy: [{ property: 'taxRate' }], n: 1 },
+    topNWithTiesPipe1: { type: 'TopNPerGroup', pipeId: 'joinLeft1', groupBy: ['year'], orderBy: [{ property: 'taxRate' }], n: 1, ties: 'WithTies' },
+    topNInterleavedPipe1: { type: 'TopNPerGroup', pipeId: 'source1', groupBy: ['year'], orderBy: [{ property: 'month', descending: true }], n: 2 },
+    topNInterleavedWithTiesPipe1: { type: 'TopNPerGroup', pipeId: 'source1', groupBy: ['year'], orderBy: [{ property: 'month', descending: true }], n: 2, ties: 'WithTies' },
+    source5: { type: 'Source', sourceId: 'sourceId5' },
+    intervalJoinPipe1: { type: 'IntervalJoin', leftPipeId: 'source1', rightPipeId: 'source5', value: 'month', start: 'start', end: 'end' },
+    intervalJoinClosedLeftPipe1: { type: 'IntervalJoin', leftPipeId: 'source1', rightPipeId: 'source5', value: 'month', start: 'start', end: 'end', closed: 'Left', how: 'Inner' },
+    source9: { type: 'Source', sourceId: 'sourceId9' },
+    intervalJoinOverlapPipe1: { type: 'IntervalJoin', leftPipeId: 'source1', rightPipeId: 'source9', value: 'month', start: 'start', end: 'end' },
+    source10: { type: 'Source', sourceId: 'sourceId10' },
+    intervalJoinAdjoiningPipe1: { type: 'IntervalJoin', leftPipeId: 'source1', rightPipeId: 'source10', value: 'month', start: 'start', end: 'end' },
 }
 const PIPE_CONFIGS_MAP = new Map<string, PipeConfig>(Object.entries(PIPE_CONFIGS))
 
 let runDataPipeline: any = null // Set in beforeAll as wasm must be loaded asynchronously - otherwise module won't load
+let getRootSources: any = null
+let validatePipeline: any = null
+let getPipeSchema: any = null
+let describePipePlan: any = null
 describe('Data Pipe Testing', () => {
     beforeAll((done) => {
         import('polars-pipes').then(module => {
             runDataPipeline = module.runDataPipeline
+            getRootSources = module.getRootSources
+            validatePipeline = module.validatePipeline
+            getPipeSchema = module.getPipeSchema
+            describePipePlan = module.describePipePlan
             done()
         })
     })
@@ -139,7 +291,7 @@ describe('Data Pipe Testing', () => {
     describe('Join Pipe', () => {
         describe('Left Join', () => {
             it('should return correct result when valid', () => {
-                const tableResult = runDataPipeline(['joinLeft1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['joinLeft1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinLeft1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
@@ -155,7 +307,7 @@ describe('Data Pipe Testing', () => {
         })
         describe('Inner Join', () => {
             it('should return correct result when valid', () => {
-                const tableResult = runDataPipeline(['joinInner1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['joinInner1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinInner1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
@@ -169,7 +321,7 @@ describe('Data Pipe Testing', () => {
         })
         describe('Right Join', () => {
             it('should return correct result when valid', () => {
-                const tableResult = runDataPipeline(['joinRight1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['joinRight1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinRight1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
@@ -184,7 +336,7 @@ describe('Data Pipe Testing', () => {
         })
         describe('Outer Join', () => {
             it('should return correct result when valid', () => {
-                const tableResult = runDataPipeline(['joinOuter1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['joinOuter1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinOuter1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
@@ -199,12 +351,605 @@ describe('Data Pipe Testing', () => {
                 expect(arrayResult).toEqual(expectedResult)
             })
         })
+        describe('Semi Join', () => {
+            it('should keep left rows with a match and only left columns', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinSemi1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinSemi1'))
+                expect(arrayResult).toEqual([
+                    { year: 2021, month: 1, revenue: 100, cost: 50 },
+                    { year: 2021, month: 2, revenue: 200, cost: 100 },
+                    { year: 2022, month: 2, revenue: 300, cost: 150 },
+                    { year: 2022, month: 3, revenue: 400, cost: 200 },
+                ])
+            })
+        })
+        describe('Anti Join', () => {
+            it('should keep left rows without a match and only left columns', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinAnti1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinAnti1'))
+                expect(arrayResult).toEqual([
+                    { year: 2023, month: 3, revenue: 500, cost: 250 },
+                    { year: 2023, month: 4, revenue: 600, cost: 300 },
+                ])
+            })
+        })
+        describe('Differently Named Keys', () => {
+            it('should join leftOn columns to rightOn columns', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinLeftOnRightOn1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinLeftOnRightOn1'))
+                expect(arrayResult.map(x => [x.year, x.month, x.taxRate])).toEqual([[2021, 1, 0.15], [2021, 2, 0.15], [2022, 2, 0.25], [2022, 3, 0.25]])
+            })
+            it('should add the suffix to colliding right columns', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinSuffix1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinSuffix1'))
+                expect(arrayResult).toEqual([
+                    { year: 2021, month: 1, revenue: 100, cost: 50, revenue_head: 100, cost_head: 50 },
+                    { year: 2021, month: 2, revenue: 200, cost: 100, revenue_head: 200, cost_head: 100 },
+                ])
+            })
+        })
+        describe('Cardinality Validation', () => {
+            it('should succeed when the data matches the declared relationship', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinManyToOne1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinManyToOne1'))
+                expect(arrayResult).toEqual(fromDataTypeArrays(runDataPipeline(['joinLeft1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinLeft1')))
+            })
+            it('should fail with sample keys when the data violates the declared relationship', () => {
+                expect(() => runDataPipeline(['joinOneToOne1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                    return e.kind === 'Cardinality' && e.pipeId === 'joinOneToOne1' && e.message.includes('3 keys appear more than once in the left pipe')
+                        && e.message.includes('(year=2021)')
+                })
+            })
+            it('should reject validation of Cross and AsOf joins', () => {
+                for (const pipeId of ['joinAsOfValidated1', 'joinCrossValidated1']) {
+                    expect(() => runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                        return e.kind === 'InvalidInput' && e.pipeId === pipeId && e.path[0] === 'validate'
+                    })
+                    const summary = validatePipeline(PIPE_CONFIGS_MAP, [pipeId]).map((d: any) => [d.kind, d.pipeId, d.path.join('.')])
+                    expect(summary).toEqual([['InvalidInput', pipeId, 'validate']])
+                }
+            })
+        })
+        describe('AsOf Join', () => {
+            const taxRates = (pipeId: string) => fromDataTypeArrays(runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get(pipeId)).map(x => x.taxRate)
+            it('should match the last earlier or equal key by default', () => {
+                expect(taxRates('joinAsOf1')).toEqual([0.15, 0.15, 0.25, 0.25, 0.25, 0.25])
+            })
+            it('should match the next later or equal key with the Forward strategy', () => {
+                expect(taxRates('joinAsOfForward1')).toEqual([0.15, 0.15, 0.25, 0.25, undefined, undefined])
+            })
+            it('should not match keys further apart than the tolerance', () => {
+                expect(taxRates('joinAsOfTolerance1')).toEqual([0.15, 0.15, 0.25, 0.25, undefined, undefined])
+            })
+            const statuses = (pipeId: string) => fromDataTypeArrays(runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get(pipeId)).map(x => x.status)
+            it('should apply the tolerance in milliseconds to datetime keys', () => {
+                expect(statuses('joinAsOfDatetime1')).toEqual([undefined, 'a1', undefined])
+            })
+            it('should reject a fractional tolerance for datetime keys', () => {
+                expect(() => runDataPipeline(['joinAsOfFractionalTolerance1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                    return e.kind === 'InvalidInput' && e.pipeId === 'joinAsOfFractionalTolerance1' && e.path[0] === 'tolerance'
+                })
+            })
+            it('should only match rows with equal by columns', () => {
+                expect(statuses('joinAsOfBy1')).toEqual(['a0', 'b0', 'a1'])
+            })
+        })
+        describe('Cross Join', () => {
+            it('should pair every left row with every right row', () => {
+                const arrayResult = fromDataTypeArrays(runDataPipeline(['joinCross1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinCross1'))
+                expect(arrayResult.length).toEqual(9)
+                expect(arrayResult[0]).toEqual({ year: 2020, taxRate: 0.05, code: '1', flag: 'true' })
+            })
+            it('should fail when the output would exceed maxRows', () => {
+                expect(() => runDataPipeline(['joinCrossLimited1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                    return e.kind === 'RowLimit' && e.pipeId === 'joinCrossLimited1' && e.path[0] === 'maxRows'
+                })
+            })
+            it('should reject leftOn and rightOn', () => {
+                expect(() => runDataPipeline(['joinCrossKeys1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                    return e.kind === 'InvalidInput' && e.pipeId === 'joinCrossKeys1' && e.path[0] === 'leftOn'
+                })
+                const summary = validatePipeline(PIPE_CONFIGS_MAP, ['joinCrossKeys1']).map((d: any) => [d.kind, d.pipeId, d.path.join('.')])
+                expect(summary).toEqual([['InvalidInput', 'joinCrossKeys1', 'leftOn']])
+            })
+        })
+    })
+
+    describe('Multiple Endpoints', () => {
+        it('should return a result for every requested pipe', () => {
+            const tableResults = runDataPipeline(['joinInner1', 'addPipe1', 'multiplyPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+
+            expect([...tableResults.keys()].sort()).toEqual(['addPipe1', 'joinInner1', 'multiplyPipe1'])
+            expect(fromDataTypeArrays(tableResults.get('joinInner1'))).toEqual(fromDataTypeArrays(runDataPipeline(['joinInner1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('joinInner1')))
+            expect(fromDataTypeArrays(tableResults.get('addPipe1'))).toEqual(fromDataTypeArrays(runDataPipeline(['addPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('addPipe1')))
+        })
+    })
+
+    describe('Shared Subgraphs', () => {
+        // Each level splits the previous level into two branches and joins them back together, so without
+        // caching the source would be computed 2^depth times
+        const diamondConfigs = (depth: number) => {
+            const configs: {[k: string]: PipeConfig} = { level0: { type: 'Source', sourceId: 'sourceId2' } }
+            for (let i = 1; i <= depth; i++) {
+                configs[`left${i}`] = {
+                    type: 'Filter',
+                    pipeId: `level${i - 1}`,
+                    filters: [{ operation: 'GreaterThan', operands: [{ property: 'taxRate' }, 0] }],
+                }
+                configs[`right${i}`] = {
+                    type: 'Filter',
+                    pipeId: `level${i - 1}`,
+                    filters: [{ operation: 'GreaterThan', operands: [{ property: 'year' }, 0] }],
+                }
+                configs[`level${i}`] = {
+                    type: 'Join',
+                    leftPipeId: `left${i}`,
+                    rightPipeId: `right${i}`,
+                    on: ['year', 'taxRate'],
+                    how: 'Inner',
+                }
+            }
+            return new Map(Object.entries(configs))
+        }
+        const expectedResult = [
+            { year: 2020, taxRate: 0.05 },
+            { year: 2021, taxRate: 0.15 },
+            { year: 2022, taxRate: 0.25 },
+        ]
+        it('should cache a pipe shared by the branches of a single diamond', () => {
+            const plan: string = describePipePlan('level1', INPUT_TABLE_MAP, diamondConfigs(1))
+            expect(plan.match(/CACHE/g)?.length).toEqual(2)
+            expect(fromDataTypeArrays(runDataPipeline(['level1'], INPUT_TABLE_MAP, diamondConfigs(1)).get('level1'))).toEqual(expectedResult)
+        })
+        it('should cache each level of nested diamonds under a single id', () => {
+            for (const depth of [2, 3, 4]) {
+                const plan: string = describePipePlan(`level${depth}`, INPUT_TABLE_MAP, diamondConfigs(depth))
+                const cacheIds = (plan.match(/CACHE\[id: \w+/g) ?? []).map(x => x.slice('CACHE[id: '.length))
+                // The printed plan repeats a cached level under every reference to it, but all of them share one
+                // cache, so each shared level is computed once
+                expect(cacheIds.length).toEqual(2 ** (depth + 1) - 2)
+                expect(new Set(cacheIds).size).toEqual(depth)
+            }
+        })
+        it('should evaluate nested fan-out/fan-in graphs', () => {
+            const tableResult = runDataPipeline(['level10'], INPUT_TABLE_MAP, diamondConfigs(10)).get('level10')
+            expect(fromDataTypeArrays(tableResult)).toEqual(expectedResult)
+        })
+    })
+
+    describe('Cycles', () => {
+        const CYCLIC_CONFIGS_MAP = new Map<string, PipeConfig>(Object.entries({
+            ...PIPE_CONFIGS,
+            cycleA: { type: 'Join', leftPipeId: 'source1', rightPipeId: 'cycleB', on: ['year'], how: 'Left' },
+            cycleB: { type: 'Rename', pipeId: 'cycleC', properties: [] },
+            cycleC: { type: 'Filter', pipeId: 'cycleA', filters: [] },
+        } as {[k: string]: PipeConfig}))
+
+        it('should name the cycle path when running a pipeline', () => {
+            expect(() => runDataPipeline(['cycleA'], INPUT_TABLE_MAP, CYCLIC_CONFIGS_MAP))
+                .toThrowMatching((e: any) => e.kind === 'Cycle' && e.message.includes('cycleA -> cycleB -> cycleC -> cycleA'))
+        })
+        it('should name the cycle path when getting root sources', () => {
+            expect(() => getRootSources(CYCLIC_CONFIGS_MAP, 'cycleB'))
+                .toThrowMatching((e: any) => e.kind === 'Cycle' && e.message.includes('cycleB -> cycleC -> cycleA -> cycleB'))
+        })
+    })
+
+    describe('Errors', () => {
+        it('should report the pipe and config path of a bad operand count', () => {
+            const configs = new Map<string, PipeConfig>(Object.entries({
+                ...PIPE_CONFIGS,
+                badNot: {
+                    type: 'DerivedValues',
+                    pipeId: 'source1',
+                    calcs: [{
+                        name: 'notValue',
+                        expression: { operation: 'Not', operands: [{ property: 'revenue' }, { property: 'cost' }] },
+                    }],
+                },
+            } as {[k: string]: PipeConfig}))
+            expect(() => runDataPipeline(['badNot'], INPUT_TABLE_MAP, configs)).toThrowMatching((e: any) => {
+                return e.kind === 'OperandCount'
+                    && e.pipeId === 'badNot'
+                    && JSON.stringify(e.path) === JSON.stringify(['calcs', '0', 'expression', 'operands'])
+            })
+        })
+        it('should report the referencing pipe when an upstream pipe is missing', () => {
+            const configs = new Map<string, PipeConfig>(Object.entries({
+                ...PIPE_CONFIGS,
+                danglingFilter: { type: 'Filter', pipeId: 'doesNotExist', filters: [] },
+            } as {[k: string]: PipeConfig}))
+            expect(() => runDataPipeline(['danglingFilter'], INPUT_TABLE_MAP, configs)).toThrowMatching((e: any) => {
+                return e.kind === 'MissingPipe' && e.pipeId === 'danglingFilter' && e.path[0] === 'pipeId'
+            })
+        })
+    })
+
+    describe('Validation', () => {
+        it('should report every problem in the graph at once', () => {
+            const configs = new Map<string, PipeConfig>(Object.entries({
+                ...PIPE_CONFIGS,
+                badCalcs: {
+                    type: 'DerivedValues',
+                    pipeId: 'source1',
+                    calcs: [
+                        { name: 'a', expression: { operation: 'Not', operands: [1, 2] } },
+                        { name: 'a', expression: { operation: 'IfThenElse', operands: [1, 2] } },
+                    ],
+                },
+                badGroup: { type: 'GroupAndReduce', pipeId: 'badCalcs', groupBy: [], aggs: [] },
+                badJoin: { type: 'Join', leftPipeId: 'badGroup', rightPipeId: 'missingPipe', on: [], how: 'Left' },
+            } as {[k: string]: PipeConfig}))
+            const diagnostics = validatePipeline(configs, ['badJoin'])
+            const summary = diagnostics.map((d: any) => [d.kind, d.pipeId, d.path.join('.')])
+
+            expect(summary).toEqual(jasmine.arrayWithExactContents([
+                ['OperandCount', 'badCalcs', 'calcs.0.expression.operands'],
+                ['OperandCount', 'badCalcs', 'calcs.1.expression.operands'],
+                ['DuplicateName', 'badCalcs', 'calcs.1.name'],
+                ['OperandCount', 'badGroup', 'groupBy'],
+                ['MissingPipe', 'badJoin', 'rightPipeId'],
+                ['OperandCount', 'badJoin', 'on'],
+            ]))
+        })
+        it('should return no diagnostics for a valid graph', () => {
+            expect(validatePipeline(PIPE_CONFIGS_MAP, ['joinLeft1', 'addPipe1'])).toEqual([])
+        })
+    })
+
+    describe('Schema Inference', () => {
+        const INPUT_SCHEMAS = new Map(Object.entries({
+            sourceId1: { i64: ['year', 'month'], f64: ['revenue', 'cost'] },
+            sourceId2: { i64: ['year'], f64: ['taxRate'] },
+        }))
+
+        it('should return the output columns of a pipe without data', () => {
+            const result = getPipeSchema(PIPE_CONFIGS_MAP, INPUT_SCHEMAS, 'joinLeft1')
+
+            expect(result.missingColumns).toEqual([])
+            expect(result.schema.i64).toEqual(['year', 'month'])
+            expect(result.schema.f64).toEqual(['revenue', 'cost', 'taxRate'])
+            expect(result.schema.str).toEqual([])
+        })
+        it('should report referenced columns which do not exist', () => {
+            const configs = new Map<string, PipeConfig>(Object.entries({
+                ...PIPE_CONFIGS,
+                badReference: {
+                    type: 'DerivedValues',
+                    pipeId: 'source1',
+                    calcs: [{ name: 'profit', expression: { operation: 'Subtract', operands: [{ property: 'revenue' }, { property: 'costs' }] } }],
+                },
+            } as {[k: string]: PipeConfig}))
+            const result = getPipeSchema(configs, INPUT_SCHEMAS, 'badReference')
+
+            expect(result.schema).toBeUndefined()
+            expect(result.missingColumns.map((d: any) => [d.kind, d.pipeId, d.path.join('.')])).toEqual([
+                ['MissingColumn', 'badReference', 'calcs.0.expression.operands.1.property'],
+            ])
+        })
+        it('should not resolve pipes whose output columns depend on the data', () => {
+            const configs = new Map<string, PipeConfig>(Object.entries({
+                ...PIPE_CONFIGS,
+                afterPivot: { type: 'Select', pipeId: 'pivotDataPipe1', properties: ['year', '1'] },
+            } as {[k: string]: PipeConfig}))
+            const result = getPipeSchema(configs, INPUT_SCHEMAS, 'afterPivot')
+
+            expect(result.schema).toBeUndefined()
+            expect(result.missingColumns).toEqual([])
+            expect(result.dataDependent.map((d: any) => [d.kind, d.pipeId, d.path.join('.')])).toEqual([
+                ['DataDependentSchema', 'pivotDataPipe1', 'pivotValues'],
+            ])
+        })
+    })
+
+    describe('Sort Pipe', () => {
+        it('should sort by multiple keys in their given directions', () => {
+            const tableResult = runDataPipeline(['sortPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('sortPipe1')
+            const arrayResult = fromDataTypeArrays(tableResult)
+
+            const expectedResult = [
+                { year: 2023, month: 3, revenue: 500, cost: 250 },
+                { year: 2023, month: 4, revenue: 600, cost: 300 },
+                { year: 2022, month: 2, revenue: 300, cost: 150 },
+                { year: 2022, month: 3, revenue: 400, cost: 200 },
+                { year: 2021, month: 1, revenue: 100, cost: 50 },
+                { year: 2021, month: 2, revenue: 200, cost: 100 },
+            ]
+            expect(arrayResult).toEqual(expectedResult)
+        })
+    })
+
+    describe('Slice Pipe', () => {
+        it('should take the first rows', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['headPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('headPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, month: 1, revenue: 100, cost: 50 },
+                { year: 2021, month: 2, revenue: 200, cost: 100 },
+            ])
+        })
+        it('should take the last rows when offset is negative', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['tailPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('tailPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2023, month: 3, revenue: 500, cost: 250 },
+                { year: 2023, month: 4, revenue: 600, cost: 300 },
+            ])
+        })
+    })
+
+    describe('Select and Drop Pipes', () => {
+        it('should keep only the selected columns', () => {
+            const tableResult = runDataPipeline(['selectPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('selectPipe1')
+            expect([...tableResult.f64.keys(), ...tableResult.i64.keys()]).toEqual(['cost', 'year'])
+        })
+        it('should drop columns matching a glob', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['dropPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('dropPipe1'))
+            expect(arrayResult[0]).toEqual({ month: 1, cost: 50 })
+        })
+        it('should list every column which does not exist', () => {
+            expect(() => runDataPipeline(['selectMissingPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'MissingColumn' && e.message.includes('profit, tax')
+            })
+        })
+    })
+
+    describe('Union Pipe', () => {
+        it('should stack pipes by name, filling missing columns with nulls', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['unionByNamePipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('unionByNamePipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, month: 1, revenue: 100, cost: 50, taxRate: undefined, from: 'headPipe1' },
+                { year: 2021, month: 2, revenue: 200, cost: 100, taxRate: undefined, from: 'headPipe1' },
+                { year: 2020, month: undefined, revenue: undefined, cost: undefined, taxRate: 0.05, from: 'source2' },
+                { year: 2021, month: undefined, revenue: undefined, cost: undefined, taxRate: 0.15, from: 'source2' },
+                { year: 2022, month: undefined, revenue: undefined, cost: undefined, taxRate: 0.25, from: 'source2' },
+            ])
+        })
+        it('should error in strict mode when columns differ', () => {
+            expect(() => runDataPipeline(['unionStrictPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'TypeMismatch' && e.pipeId === 'unionStrictPipe1'
+            })
+        })
+        it('should reject a source column which is already a column of a pipe', () => {
+            expect(() => runDataPipeline(['unionSourceClashPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'DuplicateName' && e.pipeId === 'unionSourceClashPipe1' && e.path[0] === 'sourceColumn'
+            })
+            const summary = validatePipeline(PIPE_CONFIGS_MAP, ['unionSourceClashPipe1']).map((d: any) => [d.kind, d.pipeId, d.path.join('.')])
+            expect(summary).toEqual([['DuplicateName', 'unionSourceClashPipe1', 'sourceColumn']])
+        })
+        it('should include every parent in the root sources', () => {
+            expect(getRootSources(PIPE_CONFIGS_MAP, 'unionByNamePipe1')).toEqual(['source1', 'source2'])
+        })
+    })
+
+    describe('Pivot Pipe', () => {
+        it('should create one column per pivot value', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['pivotPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('pivotPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, '1': 100, '2': 200, '3': undefined },
+                { year: 2022, '1': undefined, '2': 300, '3': 400 },
+                { year: 2023, '1': undefined, '2': undefined, '3': 500 },
+            ])
+        })
+        it('should create a column per distinct value found in the data when no pivotValues are given', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['pivotDataPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('pivotDataPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, '1': 100, '2': 200, '3': undefined, '4': undefined },
+                { year: 2022, '1': undefined, '2': 300, '3': 400, '4': undefined },
+                { year: 2023, '1': undefined, '2': undefined, '3': 500, '4': 600 },
+            ])
+        })
+        it('should match pivotValues to numeric column values', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['pivotFloatPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('pivotFloatPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, '1': 100, '2': 200 },
+                { year: 2022, '1': undefined, '2': 300 },
+                { year: 2023, '1': undefined, '2': undefined },
+            ])
+        })
+        it('should reject pivot values which are also index columns', () => {
+            expect(() => runDataPipeline(['pivotIndexClashPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'DuplicateName' && e.pipeId === 'pivotIndexClashPipe1' && e.path.join('.') === 'pivotValues.1'
+            })
+            const summary = validatePipeline(PIPE_CONFIGS_MAP, ['pivotIndexClashPipe1']).map((d: any) => [d.kind, d.pipeId, d.path.join('.')])
+            expect(summary).toEqual([['DuplicateName', 'pivotIndexClashPipe1', 'pivotValues.1']])
+        })
+    })
+
+    describe('Unpivot Pipe', () => {
+        it('should create one row per id and value column', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['unpivotPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('unpivotPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, month: 1, measure: 'revenue', amount: 100 },
+                { year: 2021, month: 2, measure: 'revenue', amount: 200 },
+                { year: 2021, month: 1, measure: 'cost', amount: 50 },
+                { year: 2021, month: 2, measure: 'cost', amount: 100 },
+            ])
+        })
+        it('should unpivot the non-id columns matching a pattern', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['unpivotGlobPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('unpivotGlobPipe1'))
+            expect(arrayResult.map(x => [x.month, x.variable, x.value])).toEqual([[1, 'revenue', 100], [2, 'revenue', 200]])
+        })
+        it('should fail when the value column pattern only matches id columns', () => {
+            expect(() => runDataPipeline(['unpivotOnlyIdsPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'MissingColumn' && e.pipeId === 'unpivotOnlyIdsPipe1' && e.path[0] === 'valueColumns'
+            })
+        })
+    })
+
+    describe('Distinct Pipe', () => {
+        it('should keep the last row for each duplicated subset in input order', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['distinctLastPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('distinctLastPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, month: 2, revenue: 200, cost: 100 },
+                { year: 2022, month: 3, revenue: 400, cost: 200 },
+                { year: 2023, month: 4, revenue: 600, cost: 300 },
+            ])
+        })
+    })
+
+    describe('Cast Pipe', () => {
+        it('should null out values which cannot be converted', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['castPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('castPipe1'))
+            expect(arrayResult).toEqual([
+                { code: 1, flag: true },
+                { code: undefined, flag: false },
+                { code: 3, flag: undefined },
+            ])
+        })
+        it('should report how many values failed to convert alongside the converted table', () => {
+            const tableResult = runDataPipeline(['castCountPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('castCountPipe1')
+            expect(fromDataTypeArrays(tableResult)).toEqual([
+                { code: 1, flag: true },
+                { code: undefined, flag: false },
+                { code: 3, flag: undefined },
+            ])
+            const counts = tableResult.castFailures.get('castCountPipe1')
+            expect(counts.get('code')).toEqual(1)
+            expect(counts.get('flag')).toEqual(1)
+        })
+        it('should report failure counts on tables downstream of the Cast pipe only', () => {
+            const result = runDataPipeline(['castCountSelectPipe1', 'source3'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+            expect([...result.get('castCountSelectPipe1').castFailures.keys()]).toEqual(['castCountPipe1'])
+            expect(result.get('source3').castFailures.size).toEqual(0)
+        })
+        it('should parse strings into dates and datetimes with a format', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['castDatesPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('castDatesPipe1'))
+            expect(arrayResult).toEqual([
+                { day: Date.UTC(2021, 0, 2), at: Date.UTC(2021, 0, 2, 3, 4) },
+                { day: undefined, at: undefined },
+            ])
+        })
+        it('should format dates as strings', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['formatDatePipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('formatDatePipe1'))
+            expect(arrayResult.map(x => x.day)).toEqual(['02/01/2021', undefined])
+        })
+        it('should fail when strict and a value cannot be converted', () => {
+            expect(() => runDataPipeline(['castStrictPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP))
+                .toThrowMatching((e: any) => e.kind === 'TypeMismatch' && e.pipeId === 'castStrictPipe1' && e.message.includes('1 values in column code'))
+        })
+    })
+
+    describe('StringToDate Pipe', () => {
+        it('should parse a string column into a new date column', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['stringToDatePipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('stringToDatePipe1'))
+            expect(arrayResult).toEqual([{ day: '2021-01-02', at: '2021-01-02 03:04', date: Date.UTC(2021, 0, 2) }])
+        })
+    })
+
+    describe('FillNull Pipe', () => {
+        const taxRates = (pipeId: string) => fromDataTypeArrays(runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get(pipeId)).map(x => x.taxRate)
+        it('should fill with a literal', () => {
+            expect(taxRates('fillNullLiteralPipe1')).toEqual([0.15, 0.15, 0.25, 0.25, 0.5, 0.5])
+        })
+        it('should fill forward', () => {
+            expect(taxRates('fillNullForwardPipe1')).toEqual([0.15, 0.15, 0.25, 0.25, 0.25, 0.25])
+        })
+        it('should only fill from the same group when over is given', () => {
+            expect(taxRates('fillNullForwardOverPipe1')).toEqual([0.15, 0.15, 0.25, 0.25, undefined, undefined])
+        })
+    })
+
+    describe('Sample Pipe', () => {
+        it('should return the same whole rows on every run when seeded', () => {
+            const run = () => fromDataTypeArrays(runDataPipeline(['samplePipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('samplePipe1'))
+            const arrayResult = run()
+            expect(arrayResult.length).toEqual(3)
+            arrayResult.forEach(row => expect(INPUT_DATA['sourceId1'][1]).toContain(row))
+            expect(run()).toEqual(arrayResult)
+        })
+    })
+
+    describe('List Columns', () => {
+        it('should round trip list columns', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['source4'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('source4'))
+            expect(arrayResult).toEqual(INPUT_DATA['sourceId4'][1])
+        })
+        it('should explode lists into one row per element', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['explodePipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('explodePipe1'))
+            expect(arrayResult).toEqual([
+                { name: 'a', tags: 'x' },
+                { name: 'a', tags: 'y' },
+                { name: 'b', tags: undefined },
+                { name: 'c', tags: 'z' },
+            ])
+        })
+        it('should collect group values into lists', () => {
+            const arrayResult = fromDataTypeArrays(runDataPipeline(['listAggSortPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('listAggSortPipe1'))
+            expect(arrayResult).toEqual([
+                { year: 2021, months: [1, 2] },
+                { year: 2022, months: [2, 3] },
+                { year: 2023, months: [3, 4] },
+            ])
+        })
+    })
+
+    describe('TopNPerGroup Pipe', () => {
+        const yearMonths = (pipeId: string) => fromDataTypeArrays(runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get(pipeId)).map(x => [x.year, x.month])
+        it('should keep the top rows of each group in order', () => {
+            expect(yearMonths('topNPipe1')).toEqual([[2023, 4], [2022, 3], [2021, 2]])
+        })
+        it('should keep exactly n rows per group by default', () => {
+            expect(yearMonths('topNFirstPipe1')).toEqual([[2023, 3], [2021, 1], [2022, 2]])
+        })
+        it('should keep rows tied with the nth row when requested', () => {
+            expect(yearMonths('topNWithTiesPipe1')).toEqual([[2023, 3], [2023, 4], [2021, 1], [2021, 2], [2022, 2], [2022, 3]])
+        })
+        it('should count positions within each group when groups interleave in the ordering', () => {
+            const expected = [[2023, 4], [2022, 3], [2023, 3], [2021, 2], [2022, 2], [2021, 1]]
+            expect(yearMonths('topNInterleavedPipe1')).toEqual(expected)
+            expect(yearMonths('topNInterleavedWithTiesPipe1')).toEqual(expected)
+        })
+    })
+
+    describe('IntervalJoin Pipe', () => {
+        const monthPeriods = (pipeId: string) => fromDataTypeArrays(runDataPipeline([pipeId], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get(pipeId))
+            .map(x => `${x.year}-${x.month}:${x.period}`)
+            .sort()
+        it('should match values inside inclusive intervals and keep unmatched rows', () => {
+            expect(monthPeriods('intervalJoinPipe1')).toEqual([
+                '2021-1:early', '2021-2:early', '2022-2:early', '2022-3:late', '2023-3:late', '2023-4:undefined',
+            ])
+        })
+        it('should respect exclusive ends and drop unmatched rows for inner joins', () => {
+            expect(monthPeriods('intervalJoinClosedLeftPipe1')).toEqual(['2021-1:early'])
+        })
+        it('should return a row for each overlapping interval containing a value', () => {
+            expect(monthPeriods('intervalJoinOverlapPipe1')).toEqual([
+                '2021-1:year', '2021-2:year', '2022-2:year', '2022-3:spring', '2022-3:year',
+                '2023-3:spring', '2023-3:year', '2023-4:spring', '2023-4:year',
+            ])
+        })
+        it('should match a shared boundary to both adjoining closed intervals', () => {
+            expect(monthPeriods('intervalJoinAdjoiningPipe1')).toEqual([
+                '2021-1:first', '2021-2:first', '2021-2:second', '2022-2:first', '2022-2:second',
+                '2022-3:second', '2023-3:second', '2023-4:undefined',
+            ])
+        })
+    })
+
+    describe('Column Order', () => {
+        it('should keep the input column order', () => {
+            const tableResult = runDataPipeline(['source1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('source1')
+            expect(tableResult.columnOrder).toEqual(['year', 'month', 'revenue', 'cost'])
+            expect(Object.keys(fromDataTypeArrays(tableResult)[0])).toEqual(['year', 'month', 'revenue', 'cost'])
+        })
+        it('should return columns in the order chosen by a Select pipe', () => {
+            const tableResult = runDataPipeline(['selectPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('selectPipe1')
+            expect(tableResult.columnOrder).toEqual(['cost', 'year'])
+        })
+        it('should order columns by name when no columnOrder is given', () => {
+            const inputs = new Map(INPUT_TABLE_MAP)
+            inputs.set('sourceId1', { ...INPUT_TABLE_MAP.get('sourceId1')!, columnOrder: [] })
+            const tableResult = runDataPipeline(['source1'], inputs, PIPE_CONFIGS_MAP).get('source1')
+            expect(tableResult.columnOrder).toEqual(['cost', 'month', 'revenue', 'year'])
+        })
+        it('should fail when columnOrder names a missing column', () => {
+            const inputs = new Map(INPUT_TABLE_MAP)
+            inputs.set('sourceId1', { ...INPUT_TABLE_MAP.get('sourceId1')!, columnOrder: ['year', 'profit'] })
+            expect(() => runDataPipeline(['source1'], inputs, PIPE_CONFIGS_MAP)).toThrowMatching((e: any) => {
+                return e.kind === 'MissingColumn' && e.path.join('.') === 'inputData.sourceId1.columnOrder.1'
+            })
+        })
     })
 
     describe('Derived Values Pipe', () => {
         describe('Addition', () => {
             it('should add correctly', () => {
-                const tableResult = runDataPipeline(['addPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['addPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('addPipe1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
@@ -221,7 +966,7 @@ describe('Data Pipe Testing', () => {
 
         describe('Multiplication', () => {
             it('should multiply correctly', () => {
-                const tableResult = runDataPipeline(['multiplyPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP)
+                const tableResult = runDataPipeline(['multiplyPipe1'], INPUT_TABLE_MAP, PIPE_CONFIGS_MAP).get('multiplyPipe1')
                 const arrayResult = fromDataTypeArrays(tableResult)
 
                 const expectedResult = [
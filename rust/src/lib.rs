@@ -1,9 +1,13 @@
 use wasm_bindgen::prelude::*;
 
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use polars::{prelude::{LazyFrame, col, lit, JoinBuilder, JoinType, DataType, DataFrame, Series, NamedFrom, IntoLazy, min_horizontal, max_horizontal, TimeUnit}, lazy::dsl::{Expr, when}};
+use std::fmt;
+use std::sync::Arc;
+use polars::{prelude::{LazyFrame, col, lit, JoinBuilder, JoinType, DataType, DataFrame, Series, NamedFrom, IntoLazy, min_horizontal, max_horizontal, TimeUnit, collect_all, PolarsError, Schema, IdxSize, NULL, concat, MeltArgs, UniqueKeepStrategy, StrptimeOptions, PolarsResult, count, AsOfOptions, AsofStrategy, AnyValue}, lazy::dsl::{Expr, when}};
 
 use serde::{Deserialize, Serialize};
+use regex::Regex;
 use chrono::Utc;
 
 extern crate console_error_panic_hook;
@@ -18,6 +22,436 @@ extern "C" {
     fn log(s: &str);
 }
 
+/// TypeScript types of the values passed to and returned from the exported functions, which take and return
+/// `JsValue`s. They are added to the generated `.d.ts` and must be kept in step with the serde types below.
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+export interface DataTable {
+    f64: Map<string, (number | null)[]>,
+    i64: Map<string, (number | null)[]>,
+    str: Map<string, (string | null)[]>,
+    datetime: Map<string, (number | null)[]>,
+    bool: Map<string, (boolean | null)[]>,
+    list_f64: Map<string, ((number | null)[] | null)[]>,
+    list_i64: Map<string, ((number | null)[] | null)[]>,
+    list_str: Map<string, ((string | null)[] | null)[]>,
+    list_bool: Map<string, ((boolean | null)[] | null)[]>,
+    columnOrder?: string[],
+    castFailures?: Map<string, Map<string, number>>,
+}
+
+export type DataTableColumnType = Exclude<keyof DataTable, 'columnOrder' | 'castFailures'>
+
+export type TableSchema = { [T in DataTableColumnType]?: string[] }
+
+export type PipelineErrorKind = 'InvalidInput'
+    | 'MissingPipe'
+    | 'MissingSource'
+    | 'MissingColumn'
+    | 'OperandCount'
+    | 'DuplicateName'
+    | 'TypeMismatch'
+    | 'UnsupportedDtype'
+    | 'Polars'
+    | 'Cycle'
+    | 'RowLimit'
+    | 'Cardinality'
+    | 'DataDependentSchema'
+
+export interface PipelineError {
+    kind: PipelineErrorKind,
+    pipeId?: string,
+    path: string[],
+    message: string,
+}
+
+export interface PipeSchema {
+    schema?: TableSchema,
+    missingColumns: PipelineError[],
+    dataDependent: PipelineError[],
+}
+
+export type PipeConfig = SourcePipeConfig
+    | DerivedValuesPipeConfig
+    | GroupAndReducePipeConfig
+    | FilterPipeConfig
+    | JoinPipeConfig
+    | RenamePipeConfig
+    | SortPipeConfig
+    | SlicePipeConfig
+    | SelectPipeConfig
+    | DropPipeConfig
+    | UnionPipeConfig
+    | PivotPipeConfig
+    | UnpivotPipeConfig
+    | DistinctPipeConfig
+    | CastPipeConfig
+    | StringToDatePipeConfig
+    | IntervalJoinPipeConfig
+    | TopNPerGroupPipeConfig
+    | ExplodePipeConfig
+    | SamplePipeConfig
+    | FillNullPipeConfig
+
+export interface SourcePipeConfig {
+    type: 'Source',
+    sourceId: string,
+}
+
+export interface DerivedValuesPipeConfig {
+    type: 'DerivedValues',
+    pipeId: string,
+    calcs: DerivedValuesExpressionRoot[],
+}
+
+export interface DerivedValuesExpressionRoot {
+    name: string,
+    expression: DerivedValuesExpression,
+}
+
+export type DerivedValuesExpression = DerivedValuesOperation
+    | DerivedValuesWindowAggExpression
+    | DerivedValuesProperty
+    | number
+
+export interface DerivedValuesWindowAggExpression {
+    operation: AggType,
+    operand: DerivedValuesExpression,
+    over: string[],
+}
+
+export interface DerivedValuesProperty {
+    property: string,
+}
+
+export interface DerivedValuesOperation {
+    operation: DerivedValuesOperationType,
+    operands: DerivedValuesExpression[],
+}
+
+export type DerivedValuesOperationType = 'Sum'
+    | 'Subtract'
+    | 'Multiply'
+    | 'Divide'
+    | 'Min'
+    | 'Max'
+    | 'Not'
+    | 'And'
+    | 'Or'
+    | 'LessThan'
+    | 'LessThanEq'
+    | 'GreaterThan'
+    | 'GreaterThanEq'
+    | 'IfThenElse'
+
+export interface GroupAndReducePipeConfig {
+    type: 'GroupAndReduce',
+    pipeId: string,
+    groupBy: string[],
+    aggs: AggConfig[],
+}
+
+export interface AggConfig {
+    name: string,
+    type: AggType,
+    aggProperty: string,
+}
+
+export type AggType = 'Sum'
+    | 'Max'
+    | 'Min'
+    | 'List'
+
+export interface FilterPipeConfig {
+    type: 'Filter',
+    pipeId: string,
+    filters: DerivedValuesExpression[],
+}
+
+export interface JoinPipeConfig {
+    type: 'Join',
+    leftPipeId: string,
+    rightPipeId: string,
+    on?: string[],
+    leftOn?: string[],
+    rightOn?: string[],
+    how: JoinPipeType,
+    suffix?: string,
+    validate?: JoinValidation,
+    by?: string[],
+    strategy?: AsOfJoinStrategy,
+    tolerance?: number,
+    maxRows?: number,
+}
+
+export type JoinPipeType = 'Left'
+    | 'Right'
+    | 'Inner'
+    | 'Outer'
+    | 'Semi'
+    | 'Anti'
+    | 'Cross'
+    | 'AsOf'
+
+export type AsOfJoinStrategy = 'Backward' | 'Forward' | 'Nearest'
+
+export type JoinValidation = 'OneToOne' | 'OneToMany' | 'ManyToOne' | 'ManyToMany'
+
+export interface RenamePipeConfig {
+    type: 'Rename',
+    pipeId: string,
+    properties: RenamePropertyConfig[],
+}
+
+export interface RenamePropertyConfig {
+    from: string,
+    to: string,
+}
+
+export interface SortPipeConfig {
+    type: 'Sort',
+    pipeId: string,
+    by: SortKeyConfig[],
+}
+
+export interface SortKeyConfig {
+    property: string,
+    descending?: boolean,
+    nullsLast?: boolean,
+}
+
+export interface SlicePipeConfig {
+    type: 'Slice',
+    pipeId: string,
+    offset?: number,
+    length?: number,
+}
+
+export type ColumnMatching = 'Exact' | 'Glob' | 'Regex'
+
+export interface SelectPipeConfig {
+    type: 'Select',
+    pipeId: string,
+    properties: string[],
+    matching?: ColumnMatching,
+}
+
+export interface DropPipeConfig {
+    type: 'Drop',
+    pipeId: string,
+    properties: string[],
+    matching?: ColumnMatching,
+}
+
+export interface UnionPipeConfig {
+    type: 'Union',
+    pipeIds: string[],
+    mode?: 'Strict' | 'ByName',
+    sourceColumn?: string,
+}
+
+export interface PivotPipeConfig {
+    type: 'Pivot',
+    pipeId: string,
+    index: string[],
+    columns: string,
+    values: string,
+    agg: AggType,
+    pivotValues?: string[],
+}
+
+export interface UnpivotPipeConfig {
+    type: 'Unpivot',
+    pipeId: string,
+    idColumns: string[],
+    valueColumns?: string[],
+    matching?: ColumnMatching,
+    variableName?: string,
+    valueName?: string,
+}
+
+export interface DistinctPipeConfig {
+    type: 'Distinct',
+    pipeId: string,
+    subset?: string[],
+    keep?: 'First' | 'Last' | 'None',
+    maintainOrder?: boolean,
+}
+
+export interface CastPipeConfig {
+    type: 'Cast',
+    pipeId: string,
+    columns: CastColumnConfig[],
+    strict?: boolean,
+    countFailures?: boolean,
+}
+
+export interface CastColumnConfig {
+    property: string,
+    to: CastType,
+    format?: string,
+}
+
+export type CastType = 'str' | 'i64' | 'f64' | 'bool' | 'date' | 'datetime'
+
+export interface StringToDatePipeConfig {
+    type: 'StringToDate',
+    pipeId: string,
+    columnFrom: string,
+    columnTo: string,
+    format: string,
+}
+
+export interface IntervalJoinPipeConfig {
+    type: 'IntervalJoin',
+    leftPipeId: string,
+    rightPipeId: string,
+    value: string,
+    start: string,
+    end: string,
+    by?: string[],
+    closed?: 'Both' | 'Left' | 'Right' | 'None',
+    how?: 'Left' | 'Inner',
+    suffix?: string,
+}
+
+export interface TopNPerGroupPipeConfig {
+    type: 'TopNPerGroup',
+    pipeId: string,
+    groupBy: string[],
+    orderBy: SortKeyConfig[],
+    n: number,
+    ties?: 'First' | 'WithTies',
+}
+
+export interface ExplodePipeConfig {
+    type: 'Explode',
+    pipeId: string,
+    properties: string[],
+}
+
+export interface SamplePipeConfig {
+    type: 'Sample',
+    pipeId: string,
+    n?: number,
+    fraction?: number,
+    withReplacement?: boolean,
+    seed?: number,
+}
+
+export interface FillNullPipeConfig {
+    type: 'FillNull',
+    pipeId: string,
+    rules: FillNullRule[],
+}
+
+export interface FillNullRule {
+    property: string,
+    strategy: FillNullStrategy,
+    value?: boolean | number | string,
+    column?: string,
+    over?: string[],
+}
+
+export type FillNullStrategy = 'Literal'
+    | 'Column'
+    | 'Forward'
+    | 'Backward'
+    | 'Mean'
+    | 'Min'
+    | 'Max'
+    | 'Zero'
+"#;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PipelineErrorKind {
+    /// The JS arguments could not be parsed
+    InvalidInput,
+    MissingPipe,
+    MissingSource,
+    MissingColumn,
+    OperandCount,
+    DuplicateName,
+    TypeMismatch,
+    UnsupportedDtype,
+    Polars,
+    Cycle,
+    /// An operation would produce more rows than its configured limit
+    RowLimit,
+    /// Join keys repeat on a side of a join declared to have unique keys
+    Cardinality,
+    /// The output columns of a pipe depend on its data, so can't be resolved from schemas alone
+    DataDependentSchema,
+}
+
+/// Error returned to JS from every exported function, serialized as `{ kind, pipeId, path, message }`.
+/// `pipeId` is the pipe the error occurred in, and `path` the location of the offending value within that
+/// pipe's config (e.g. `["calcs", "0", "expression", "operands", "1"]`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineError {
+    kind: PipelineErrorKind,
+    pipe_id: Option<String>,
+    path: Vec<String>,
+    message: String,
+}
+
+impl PipelineError {
+    fn new(kind: PipelineErrorKind, message: impl Into<String>) -> Self {
+        PipelineError { kind, pipe_id: None, path: vec![], message: message.into() }
+    }
+
+    /// Sets the pipe the error occurred in, unless an upstream pipe has already been recorded.
+    fn in_pipe(mut self, pipe_id: &String) -> Self {
+        if self.pipe_id.is_none() {
+            self.pipe_id = Some(pipe_id.clone());
+        }
+        self
+    }
+
+    /// Prepends segments to the config path, as the error propagates out of nested config values.
+    fn at<S: AsRef<str>>(mut self, segments: &[S]) -> Self {
+        let mut path: Vec<String> = segments.iter().map(|x| x.as_ref().to_string()).collect();
+        path.append(&mut self.path);
+        self.path = path;
+        self
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.pipe_id {
+            Some(pipe_id) => write!(f, "{:?} error in pipe {}: {}", self.kind, pipe_id, self.message),
+            None => write!(f, "{:?} error: {}", self.kind, self.message),
+        }
+    }
+}
+
+impl From<PolarsError> for PipelineError {
+    fn from(e: PolarsError) -> Self {
+        let kind = match e {
+            PolarsError::SchemaMismatch(_) => PipelineErrorKind::TypeMismatch,
+            _ => PipelineErrorKind::Polars,
+        };
+        PipelineError::new(kind, e.to_string())
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for PipelineError {
+    fn from(e: serde_wasm_bindgen::Error) -> Self {
+        PipelineError::new(PipelineErrorKind::InvalidInput, e.to_string())
+    }
+}
+
+impl From<PipelineError> for JsValue {
+    fn from(e: PipelineError) -> Self {
+        match serde_wasm_bindgen::to_value(&e) {
+            Ok(x) => x,
+            Err(_) => JsValue::from_str(&e.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SourcePipeConfig {
@@ -84,16 +518,64 @@ pub struct DerivedValuesWindowAggExpression {
     over: Vec<String>,
 }
 
-fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Expr, String> {
-    let polars_expr: Result<Expr, String> = match expression {
+fn operand_count_error(operation: &str, requirement: &str, found: usize) -> PipelineError {
+    PipelineError::new(PipelineErrorKind::OperandCount, format!("'{}' must have {} ({} found)", operation, requirement, found)).at(&["operands"])
+}
+
+/// Checks the number of operands given to an operation, without needing to build the expression.
+fn check_operand_count(operation: &DerivedValuesOperationType, found: usize) -> Result<(), PipelineError> {
+    match operation {
+        DerivedValuesOperationType::Sum
+        | DerivedValuesOperationType::Multiply
+        | DerivedValuesOperationType::And
+        | DerivedValuesOperationType::Or => Ok(()),
+        DerivedValuesOperationType::Subtract
+        | DerivedValuesOperationType::Divide => match found {
+            0 => Err(operand_count_error(&format!("{:?}", operation), "at least 1 operand", found)),
+            _ => Ok(()),
+        },
+        DerivedValuesOperationType::Min
+        | DerivedValuesOperationType::Max => match found {
+            0 => Err(operand_count_error(&format!("{:?}", operation), "at least 1 operand", found)),
+            1..=8 => Ok(()),
+            _ => Err(operand_count_error(&format!("{:?}", operation), "at most 8 operands", found)),
+        },
+        DerivedValuesOperationType::Not => match found {
+            1 => Ok(()),
+            _ => Err(operand_count_error("Not", "exactly 1 operand", found)),
+        },
+        DerivedValuesOperationType::LessThan
+        | DerivedValuesOperationType::LessThanEq
+        | DerivedValuesOperationType::GreaterThan
+        | DerivedValuesOperationType::GreaterThanEq => match found {
+            2 => Ok(()),
+            _ => Err(operand_count_error(&format!("{:?}", operation), "exactly 2 operands", found)),
+        },
+        DerivedValuesOperationType::IfThenElse => {
+            if found % 2 == 0 {
+                Err(operand_count_error("IfThenElse", "an odd number of operands", found))
+            } else if found < 3 {
+                Err(operand_count_error("IfThenElse", "at least 3 operands", found))
+            } else {
+                Ok(())
+            }
+        },
+    }
+}
+
+fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Expr, PipelineError> {
+    let polars_expr: Result<Expr, PipelineError> = match expression {
         DerivedValuesExpression::Expression(e) => {
-            let operand_polars_exprs_result = e.operands.iter()
-                .map(|operand| recurse_derived_expression(operand.clone()))
-                .collect::<Result<Vec<Expr>, String>>();
+            let operand_polars_exprs_result = e.operands.iter().enumerate()
+                .map(|(i, operand)| recurse_derived_expression(operand.clone()).map_err(|e| e.at(&["operands", i.to_string().as_str()])))
+                .collect::<Result<Vec<Expr>, PipelineError>>();
             let pl_exprs_vec: Vec<Expr> = match operand_polars_exprs_result {
                 Ok(x) => x,
                 Err(e) => return Err(e),
             };
+            if let Err(err) = check_operand_count(&e.operation, pl_exprs_vec.len()) {
+                return Err(err)
+            }
             match e.operation {
                 DerivedValuesOperationType::Sum => {
                     let sum_expr = pl_exprs_vec.iter().fold(lit(0), |acc: Expr, x: &Expr| {
@@ -121,7 +603,7 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
                 },
                 DerivedValuesOperationType::Min => {
                     let min_expr = match pl_exprs_vec.len() {
-                        0 => { return Err("'Min' requires at least one operand.".into()) },
+                        0 => { return Err(operand_count_error("Min", "at least 1 operand", 0)) },
                         1 => min_horizontal([pl_exprs_vec[0].clone()]),
                         2 => min_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone()]),
                         3 => min_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone()]),
@@ -130,13 +612,13 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
                         6 => min_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone()]),
                         7 => min_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone(), pl_exprs_vec[6].clone()]),
                         8 => min_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone(), pl_exprs_vec[6].clone(), pl_exprs_vec[7].clone()]),
-                        _ => { return Err("Too many operands to min by. Need to implement.".into()) },
+                        n => { return Err(operand_count_error("Min", "at most 8 operands", n)) },
                     };
                     Ok(min_expr)
                 },
                 DerivedValuesOperationType::Max => {
                     let max_expr = match pl_exprs_vec.len() {
-                        0 => { return Err("'Max' requires at least one operand.".into()) },
+                        0 => { return Err(operand_count_error("Max", "at least 1 operand", 0)) },
                         1 => max_horizontal([pl_exprs_vec[0].clone()]),
                         2 => max_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone()]),
                         3 => max_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone()]),
@@ -145,14 +627,14 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
                         6 => max_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone()]),
                         7 => max_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone(), pl_exprs_vec[6].clone()]),
                         8 => max_horizontal([pl_exprs_vec[0].clone(), pl_exprs_vec[1].clone(), pl_exprs_vec[2].clone(), pl_exprs_vec[3].clone(), pl_exprs_vec[4].clone(), pl_exprs_vec[5].clone(), pl_exprs_vec[6].clone(), pl_exprs_vec[7].clone()]),
-                        _ => { return Err("Too many operands to min by. Need to implement.".into()) },
+                        n => { return Err(operand_count_error("Max", "at most 8 operands", n)) },
                     };
                     Ok(max_expr)
                 },
                 DerivedValuesOperationType::Not => {
                     match pl_exprs_vec.len() {
                         1 => Ok(pl_exprs_vec[0].clone().not()),
-                        _ => return Err(format!("'Not' must have exactly 1 operand ({} found)", pl_exprs_vec.len()).into())
+                        n => return Err(operand_count_error("Not", "exactly 1 operand", n))
                     }
                 },
                 DerivedValuesOperationType::And => {
@@ -170,33 +652,28 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
                 DerivedValuesOperationType::LessThan => {
                     match pl_exprs_vec.len() {
                         2 => Ok(pl_exprs_vec[0].clone().lt(pl_exprs_vec[1].clone())),
-                        _ => return Err(format!("'LessThan' must have exactly 2 operands ({} found)", pl_exprs_vec.len()).into())
+                        n => return Err(operand_count_error("LessThan", "exactly 2 operands", n))
                     }
                 },
                 DerivedValuesOperationType::LessThanEq => {
                     match pl_exprs_vec.len() {
                         2 => Ok(pl_exprs_vec[0].clone().lt_eq(pl_exprs_vec[1].clone())),
-                        _ => return Err(format!("'LessThanEq' must have exactly 2 operands ({} found)", pl_exprs_vec.len()).into())
+                        n => return Err(operand_count_error("LessThanEq", "exactly 2 operands", n))
                     }
                 },
                 DerivedValuesOperationType::GreaterThan => {
                     match pl_exprs_vec.len() {
                         2 => Ok(pl_exprs_vec[0].clone().gt(pl_exprs_vec[1].clone())),
-                        _ => return Err(format!("'GreaterThan' must have exactly 2 operands ({} found)", pl_exprs_vec.len()).into())
+                        n => return Err(operand_count_error("GreaterThan", "exactly 2 operands", n))
                     }
                 },
                 DerivedValuesOperationType::GreaterThanEq => {
                     match pl_exprs_vec.len() {
                         2 => Ok(pl_exprs_vec[0].clone().gt_eq(pl_exprs_vec[1].clone())),
-                        _ => return Err(format!("'GreaterThanEq' must have exactly 2 operands ({} found)", pl_exprs_vec.len()).into())
+                        n => return Err(operand_count_error("GreaterThanEq", "exactly 2 operands", n))
                     }
                 },
                 DerivedValuesOperationType::IfThenElse => {
-                    if pl_exprs_vec.len() % 2 == 0 {
-                        return Err("Must have an odd number of operands".into());
-                    } else if pl_exprs_vec.len() < 3 {
-                        return Err("Not enough operands".into());
-                    }
                     // Next line is a workaround to get an initial WhenThenThen struct
                     let mut expr = when(lit(false)).then(lit(false)).when(lit(false)).then(lit(false));
                     for i in 0..(pl_exprs_vec.len() / 2) {
@@ -211,10 +688,10 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
         DerivedValuesExpression::WindowAggExpression(expr) => {
             let operand_expr = match recurse_derived_expression(*expr.operand.clone()) {
                 Ok(x) => x,
-                Err(e) => return Err(e)
+                Err(e) => return Err(e.at(&["operand"]))
             };
             if expr.over.len() > 1 {
-                return Err("Window expression over more than one variable not supported yet".into())
+                return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Window expression over more than one variable not supported yet").at(&["over"]))
             }
             let over_as_boxed_slice = expr.over.iter().map(|x| lit(x.clone())).collect::<Vec<_>>().into_boxed_slice();
             match expr.operation {
@@ -229,7 +706,8 @@ fn recurse_derived_expression(expression: DerivedValuesExpression) -> Result<Exp
                 AggType::Min => {
                     let new_expr = operand_expr.min().over(over_as_boxed_slice);
                     return Ok(new_expr)
-                }
+                },
+                AggType::List => return Err(list_agg_error().at(&["operation"])),
             }
         },
         DerivedValuesExpression::Literal(x) => Ok(lit(x)),
@@ -251,6 +729,12 @@ enum AggType {
     Sum,
     Max,
     Min,
+    /// Collects the values of each group into a list. Only supported by GroupAndReduce.
+    List,
+}
+
+fn list_agg_error() -> PipelineError {
+    PipelineError::new(PipelineErrorKind::InvalidInput, "List aggregation is only supported by GroupAndReduce")
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -274,16 +758,215 @@ pub struct FilterPipeConfig {
 pub struct JoinPipeConfig {
     left_pipe_id: String,
     right_pipe_id: String,
+    /// Columns to join on, named the same in both pipes. Not used by cross joins.
+    #[serde(default)]
     on: Vec<String>,
+    /// Columns to join on from the left pipe, matched in order with `right_on`. Used instead of `on`.
+    left_on: Option<Vec<String>>,
+    right_on: Option<Vec<String>>,
     how: JoinPipeType,
+    /// Added to the names of non-key right columns which collide with left columns. Defaults to "_right".
+    /// For Right joins, it is added to the left columns instead.
+    suffix: Option<String>,
+    /// Relationship between left and right rows which the data must satisfy. Not checked if not given.
+    validate: Option<JoinValidation>,
+    /// Columns which must be equal for rows to match in an AsOf join, named the same in both pipes
+    #[serde(default)]
+    by: Vec<String>,
+    /// Direction in which an AsOf join searches for the nearest key. Defaults to Backward.
+    strategy: Option<AsOfJoinStrategy>,
+    /// Largest distance between keys for rows to match in an AsOf join, in milliseconds for datetime keys. Must be
+    /// a whole number unless the keys are f64.
+    tolerance: Option<f64>,
+    /// Largest number of rows a cross join may produce before failing. Defaults to `DEFAULT_CROSS_JOIN_MAX_ROWS`.
+    max_rows: Option<u64>,
 }
 
+const DEFAULT_CROSS_JOIN_MAX_ROWS: u64 = 1_000_000;
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum JoinPipeType {
     Left,
     Right,
     Inner,
     Outer,
+    /// Keeps the left rows which have a match on the right, returning only left columns
+    Semi,
+    /// Keeps the left rows which have no match on the right, returning only left columns
+    Anti,
+    /// Pairs every left row with every right row
+    Cross,
+    /// Matches each left row to the right row with the nearest key, and optionally equal `by` columns
+    AsOf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum AsOfJoinStrategy {
+    /// Uses the last right row whose key is less than or equal to the left key
+    #[default]
+    Backward,
+    /// Uses the first right row whose key is greater than or equal to the left key
+    Forward,
+    Nearest,
+}
+
+fn asof_key_count_error(config: &JoinPipeConfig, found: usize) -> PipelineError {
+    let msg = format!("AsOf joins must join on exactly one column ({} found)", found);
+    PipelineError::new(PipelineErrorKind::OperandCount, msg).at(&[if config.on.is_empty() { "leftOn" } else { "on" }])
+}
+
+/// Joins each left row to the nearest right row by a single sort key. Output rows are ordered by that key.
+fn asof_join(left_lf: LazyFrame, right_lf: LazyFrame, config: &JoinPipeConfig) -> Result<LazyFrame, PipelineError> {
+    let (left_on, right_on) = match join_keys(config) {
+        Ok(x) => x,
+        Err(e) => return Err(e),
+    };
+    if left_on.len() != 1 {
+        return Err(asof_key_count_error(config, left_on.len()))
+    }
+    let (left_key, right_key) = (&left_on[0], &right_on[0]);
+    let tolerance = match (config.tolerance, left_lf.schema()?.get(left_key)) {
+        (None, _) => None,
+        (Some(x), Some(DataType::Float64)) => Some(AnyValue::Float64(x)),
+        (Some(x), Some(DataType::Int64 | DataType::Datetime(_, _))) if x.fract() != 0.0 => {
+            let msg = format!("Tolerance must be a whole number for i64 and datetime keys, not {}", x);
+            return Err(PipelineError::new(PipelineErrorKind::InvalidInput, msg).at(&["tolerance"]))
+        },
+        (Some(x), Some(DataType::Int64 | DataType::Datetime(_, _))) => Some(AnyValue::Int64(x as i64)),
+        (Some(_), dtype) => {
+            let msg = format!("Tolerance can only be used with i64, f64 or datetime keys, not {:?}", dtype);
+            return Err(PipelineError::new(PipelineErrorKind::TypeMismatch, msg).at(&["tolerance"]))
+        },
+    };
+    let strategy = match config.strategy.clone().unwrap_or_default() {
+        AsOfJoinStrategy::Backward => AsofStrategy::Backward,
+        AsOfJoinStrategy::Forward => AsofStrategy::Forward,
+        AsOfJoinStrategy::Nearest => AsofStrategy::Nearest,
+    };
+    let by: Option<Vec<_>> = match config.by.len() {
+        0 => None,
+        _ => Some(config.by.iter().map(|x| x.as_str().into()).collect()),
+    };
+    let options = AsOfOptions { strategy, tolerance, left_by: by.clone(), right_by: by, ..Default::default() };
+    // Both sides must be sorted by their key
+    let mut jb = JoinBuilder::new(left_lf.sort(left_key, Default::default()))
+        .with(right_lf.sort(right_key, Default::default()))
+        .how(JoinType::AsOf(options))
+        .left_on([col(left_key)])
+        .right_on([col(right_key)]);
+    if let Some(suffix) = &config.suffix {
+        jb = jb.suffix(suffix);
+    }
+    Ok(jb.finish())
+}
+
+/// Relationship between the rows of a join, where "one" means the keys on that side are unique.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum JoinValidation {
+    OneToOne,
+    OneToMany,
+    ManyToOne,
+    ManyToMany,
+}
+
+/// Returns an error for a cardinality validation on a join type it cannot be checked for. Cross joins have no keys,
+/// and AsOf joins match on the nearest key rather than on equal keys.
+fn join_validation_error(config: &JoinPipeConfig) -> Option<PipelineError> {
+    match (&config.validate, &config.how) {
+        (Some(_), JoinPipeType::Cross | JoinPipeType::AsOf) => {
+            let msg = format!("{:?} joins cannot be validated", config.how);
+            Some(PipelineError::new(PipelineErrorKind::InvalidInput, msg).at(&["validate"]))
+        },
+        _ => None,
+    }
+}
+
+/// Number of repeated keys shown in a cardinality error.
+const JOIN_VALIDATION_SAMPLE_KEYS: usize = 5;
+
+/// Fails if any combination of `keys` appears in more than one row of `lf`.
+fn check_unique_keys(lf: &LazyFrame, keys: &Vec<String>, side: &str, validation: &JoinValidation) -> Result<(), PipelineError> {
+    let key_exprs: Vec<Expr> = keys.iter().map(|x| col(x)).collect();
+    let repeated = lf.clone()
+        .groupby_stable(key_exprs)
+        .agg([count().alias("__rows")])
+        .filter(col("__rows").gt(lit(1)))
+        .collect()?;
+    if repeated.height() == 0 {
+        return Ok(())
+    }
+    let mut samples: Vec<String> = Vec::new();
+    for i in 0..repeated.height().min(JOIN_VALIDATION_SAMPLE_KEYS) {
+        let row = repeated.get_row(i)?;
+        let values: Vec<String> = keys.iter().zip(row.0.iter()).map(|(key, value)| format!("{}={}", key, value)).collect();
+        samples.push(format!("({})", values.join(", ")));
+    }
+    let msg = format!(
+        "Join is declared {:?} but {} keys appear more than once in the {} pipe, e.g. {}",
+        validation, repeated.height(), side, samples.join(", "),
+    );
+    Err(PipelineError::new(PipelineErrorKind::Cardinality, msg).at(&["validate"]))
+}
+
+/// Returns the left and right join columns of a join.
+fn join_keys(config: &JoinPipeConfig) -> Result<(Vec<String>, Vec<String>), PipelineError> {
+    let (left_on, right_on) = match (&config.left_on, &config.right_on) {
+        (Some(left_on), Some(right_on)) => {
+            if !config.on.is_empty() {
+                return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "Cannot give on together with leftOn and rightOn").at(&["on"]))
+            }
+            if left_on.len() != right_on.len() {
+                let msg = format!("leftOn and rightOn must have the same number of columns ({} and {} found)", left_on.len(), right_on.len());
+                return Err(PipelineError::new(PipelineErrorKind::OperandCount, msg).at(&["rightOn"]))
+            }
+            (left_on.clone(), right_on.clone())
+        },
+        (Some(_), None) => return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "leftOn must be given together with rightOn").at(&["rightOn"])),
+        (None, Some(_)) => return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "rightOn must be given together with leftOn").at(&["leftOn"])),
+        (None, None) => (config.on.clone(), config.on.clone()),
+    };
+    if left_on.is_empty() {
+        return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Must join on at least one column").at(&["on"]))
+    }
+    Ok((left_on, right_on))
+}
+
+/// Collects a frame whose data has to be inspected while the plan is built, e.g. to count rows or check keys, and
+/// returns a frame over the collected data. Using the returned frame downstream means the upstream pipes are only
+/// computed once, rather than again by the final collect.
+fn collect_for_reuse(lf: LazyFrame) -> Result<LazyFrame, PipelineError> {
+    Ok(lf.collect()?.lazy())
+}
+
+fn count_rows(lf: &LazyFrame) -> Result<u64, PipelineError> {
+    let df = lf.clone().select([count().alias("rows")]).collect()?;
+    Ok(df.column("rows")?.cast(&DataType::UInt64)?.u64()?.get(0).unwrap_or(0))
+}
+
+/// Returns an error for any join columns given to a cross join, which pairs every row regardless of keys.
+fn cross_join_keys_error(config: &JoinPipeConfig) -> Option<PipelineError> {
+    let field = match (config.on.is_empty(), &config.left_on, &config.right_on) {
+        (false, _, _) => "on",
+        (true, Some(_), _) => "leftOn",
+        (true, None, Some(_)) => "rightOn",
+        (true, None, None) => return None,
+    };
+    Some(PipelineError::new(PipelineErrorKind::InvalidInput, "Cross joins cannot have join columns").at(&[field]))
+}
+
+/// Cross joins two frames, failing rather than producing more than the configured number of rows.
+fn cross_join(left_lf: LazyFrame, right_lf: LazyFrame, config: &JoinPipeConfig) -> Result<LazyFrame, PipelineError> {
+    if let Some(e) = cross_join_keys_error(config) {
+        return Err(e)
+    }
+    let max_rows = config.max_rows.unwrap_or(DEFAULT_CROSS_JOIN_MAX_ROWS);
+    let (left_lf, right_lf) = (collect_for_reuse(left_lf)?, collect_for_reuse(right_lf)?);
+    let (left_rows, right_rows) = (count_rows(&left_lf)?, count_rows(&right_lf)?);
+    if left_rows.saturating_mul(right_rows) > max_rows {
+        let msg = format!("Cross join of {} and {} rows would produce more than the limit of {} rows", left_rows, right_rows, max_rows);
+        return Err(PipelineError::new(PipelineErrorKind::RowLimit, msg).at(&["maxRows"]))
+    }
+    Ok(left_lf.cross_join(right_lf))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -301,160 +984,1074 @@ pub struct RenamePropertyConfig {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct StringToDatePipeConfig {
+pub struct SortPipeConfig {
     pipe_id: String,
-    column_from: String,
-    column_to: String,
-    format: String,
+    by: Vec<SortKeyConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(tag="type")]
-pub enum PipeConfig {
-    Source(SourcePipeConfig),
-    DerivedValues(DerivedValuesPipeConfig),
-    GroupAndReduce(GroupAndReducePipeConfig),
-    Filter(FilterPipeConfig),
-    Join(JoinPipeConfig),
-    Rename(RenamePipeConfig),
-    // StringToDate(StringToDatePipeConfig),
+#[serde(rename_all = "camelCase")]
+pub struct SortKeyConfig {
+    property: String,
+    #[serde(default)]
+    descending: bool,
+    #[serde(default)]
+    nulls_last: bool,
 }
 
-pub struct LazyFrameFactory {
-    lazy_frames: HashMap<String, LazyFrame>,
-    pipe_configs: HashMap<String, PipeConfig>,
+/// Sorts stably by each key in turn, so rows with equal keys stay in input order.
+fn sort_by_keys(lf: LazyFrame, keys: &Vec<SortKeyConfig>) -> LazyFrame {
+    // Each key is preceded by whether it is null, so that the position of nulls can be set per key
+    let mut sort_exprs: Vec<Expr> = Vec::new();
+    let mut descending: Vec<bool> = Vec::new();
+    for key in keys.iter() {
+        sort_exprs.push(col(&key.property).is_null());
+        descending.push(!key.nulls_last);
+        sort_exprs.push(col(&key.property));
+        descending.push(key.descending);
+    }
+    lf.sort_by_exprs(sort_exprs, descending, false, true)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DataTable {
-    f64: HashMap<String, Vec<Option<f64>>>,
-    i64: HashMap<String, Vec<Option<i64>>>,
-    str: HashMap<String, Vec<Option<String>>>,
-    datetime: HashMap<String, Vec<Option<i64>>>,
-    bool: HashMap<String, Vec<Option<bool>>>,
+/// Takes `length` rows starting at `offset`. A negative offset counts back from the last row, so an offset of
+/// `-n` with no length gives the last `n` rows.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SlicePipeConfig {
+    pipe_id: String,
+    #[serde(default)]
+    offset: i64,
+    length: Option<IdxSize>,
 }
 
-fn data_table_to_frame(table: &DataTable) -> LazyFrame {
-    let mut series_vec: Vec<Series> = Vec::new();
-    table.f64.iter().for_each(|(name, values)| {
-        series_vec.push(Series::new(name, values))
-    });
-    table.i64.iter().for_each(|(name, values)| {
-        series_vec.push(Series::new(name, values))
-    });
-    table.str.iter().for_each(|(name, values)| {
-        series_vec.push(Series::new(name, values))
-    });
-    table.datetime.iter().for_each(|(name, values)| {
-        series_vec.push(Series::new(name, values))
-    });
-    DataFrame::new(series_vec).unwrap().lazy()
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelectPipeConfig {
+    pipe_id: String,
+    properties: Vec<String>,
+    #[serde(default)]
+    matching: ColumnMatching,
 }
 
-fn data_frame_to_table(lf: LazyFrame) -> Result<DataTable, String> {
-    let mut data_table = DataTable {
-        f64: HashMap::new(),
-        i64: HashMap::new(),
-        str: HashMap::new(),
-        datetime: HashMap::new(),
-        bool: HashMap::new(),
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropPipeConfig {
+    pipe_id: String,
+    properties: Vec<String>,
+    #[serde(default)]
+    matching: ColumnMatching,
+}
+
+/// How the `properties` of a Select or Drop pipe are matched against column names.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum ColumnMatching {
+    #[default]
+    Exact,
+    /// `*` matches any run of characters and `?` any single character
+    Glob,
+    /// Matches columns containing a match of the regex, so use `^...$` to match whole names
+    Regex,
+}
+
+fn column_pattern_to_regex(property: &String, matching: &ColumnMatching) -> Result<Regex, PipelineError> {
+    let pattern = match matching {
+        ColumnMatching::Exact => format!("^{}$", regex::escape(property)),
+        ColumnMatching::Glob => format!("^{}$", regex::escape(property).replace(r"\*", ".*").replace(r"\?", ".")),
+        ColumnMatching::Regex => property.clone(),
     };
+    match Regex::new(&pattern) {
+        Ok(x) => Ok(x),
+        Err(e) => Err(PipelineError::new(PipelineErrorKind::InvalidInput, format!("Invalid pattern {}: {}", property, e))),
+    }
+}
 
-    let frame = match lf.collect() {
-        Ok(x) => x,
-        Err(e) => { log(&format!("{:?}", e)); return Err(e.to_string()) },
-    };
-    let schema = frame.schema();
-    let mut schema_dtype_iter = schema.iter_dtypes();
-    let mut column_iters = frame.iter();
-    for _ in 0..frame.width() {
-        let dtype = schema_dtype_iter.next().unwrap();
-        let column = column_iters.next().unwrap();
-        match dtype {
-            DataType::Float64 => {
-                let values = column.f64().unwrap().into_iter().collect();
-                data_table.f64.insert(column.name().into(), values);
-            },
-            DataType::Int64 => {
-                let values = column.i64().unwrap().into_iter().collect();
-                data_table.i64.insert(column.name().into(), values);
-            },
-            DataType::Utf8 => {
-                let values = column.utf8().unwrap().into_iter().map(|x| match x { None => None, Some(y) => Some(y.to_string())}).collect();
-                data_table.str.insert(column.name().into(), values);
-            },
-            DataType::Boolean => {
-                let values = column.bool().unwrap().into_iter().collect();
-                data_table.bool.insert(column.name().into(), values);
-            },
-            DataType::Datetime(unit, tz) => {
-                todo!();
-                // let values: Vec<_> = match unit {
-                //     TimeUnit::Milliseconds => column.datetime().unwrap().into_iter().collect(),
-                //     TimeUnit::Microseconds => column.datetime().unwrap().into_iter().collect(),
-                //     TimeUnit::Nanoseconds => column.datetime().unwrap().into_iter().collect(),
-                // };
-                // data_table.datetime.insert(column.name().into(), values);
-            },
-            _ => return Err("!!".into()),
+/// Returns the columns of `schema` matched by `properties`, in the order of `properties`. Columns matched by a
+/// pattern are in schema order. Errors listing every property which matches no column.
+fn resolve_columns(schema: &Schema, properties: &Vec<String>, matching: &ColumnMatching) -> Result<Vec<String>, PipelineError> {
+    let mut columns: Vec<String> = Vec::new();
+    let mut missing: Vec<&String> = Vec::new();
+    for (i, property) in properties.iter().enumerate() {
+        let pattern = match column_pattern_to_regex(property, matching) {
+            Ok(x) => x,
+            Err(e) => return Err(e.at(&[i.to_string()])),
+        };
+        let matched: Vec<String> = schema.iter_names().filter(|name| pattern.is_match(name)).map(|name| name.to_string()).collect();
+        if matched.is_empty() {
+            missing.push(property);
         }
-    };
-    Ok(data_table)
+        for name in matched {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+    }
+    if !missing.is_empty() {
+        let msg = format!("No columns found matching {}", missing.iter().map(|x| x.as_str()).collect::<Vec<_>>().join(", "));
+        return Err(PipelineError::new(PipelineErrorKind::MissingColumn, msg))
+    }
+    Ok(columns)
 }
 
-#[wasm_bindgen]
+fn validate_column_patterns(properties: &Vec<String>, matching: &ColumnMatching, field: &str) -> Vec<PipelineError> {
+    properties.iter().enumerate()
+        .filter_map(|(i, property)| column_pattern_to_regex(property, matching).err().map(|e| e.at(&[field, i.to_string().as_str()])))
+        .collect()
+}
+
+/// Stacks the rows of several pipes vertically.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnionPipeConfig {
+    pipe_ids: Vec<String>,
+    #[serde(default)]
+    mode: UnionMode,
+    /// If given, a column with this name is added holding the id of the pipe each row came from
+    source_column: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum UnionMode {
+    /// Every pipe must have the same columns with the same dtypes
+    #[default]
+    Strict,
+    /// Columns are aligned by name, and columns missing from a pipe are filled with nulls
+    ByName,
+}
+
+/// Turns long data wide, with one output column per distinct value of `columns` holding the aggregated
+/// `values` for each combination of `index` values.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PivotPipeConfig {
+    pipe_id: String,
+    index: Vec<String>,
+    columns: String,
+    values: String,
+    agg: AggType,
+    /// Values of `columns` to create output columns for. If not given, the distinct values found in the data
+    /// are used, so the output columns depend on the data.
+    pivot_values: Option<Vec<String>>,
+}
+
+/// Turns wide data long, with one row per id and value column.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnpivotPipeConfig {
+    pipe_id: String,
+    id_columns: Vec<String>,
+    /// Columns to unpivot, matched according to `matching`. If empty, every column not in `id_columns` is used.
+    #[serde(default)]
+    value_columns: Vec<String>,
+    #[serde(default)]
+    matching: ColumnMatching,
+    variable_name: Option<String>,
+    value_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DistinctPipeConfig {
+    pipe_id: String,
+    /// Columns which identify a duplicate. If not given, all columns are used.
+    subset: Option<Vec<String>>,
+    #[serde(default)]
+    keep: DistinctKeep,
+    /// Keeps rows in their input order, at some cost to performance
+    #[serde(default)]
+    maintain_order: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum DistinctKeep {
+    #[default]
+    First,
+    Last,
+    /// Removes every row which has a duplicate
+    None,
+}
+
+/// Converts columns to another type.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CastPipeConfig {
+    pipe_id: String,
+    columns: Vec<CastColumnConfig>,
+    /// Fails the run if any value can't be converted, instead of replacing it with null
+    #[serde(default)]
+    strict: bool,
+    /// Counts the values in each column which can't be converted, returning them in the `castFailures` of every
+    /// requested table downstream of this pipe
+    #[serde(default)]
+    count_failures: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CastColumnConfig {
+    property: String,
+    to: CastType,
+    /// Format used when parsing strings into dates or datetimes, or when formatting them as strings, e.g. "%Y-%m-%d".
+    /// If not given when parsing, the format is inferred.
+    format: Option<String>,
+}
+
+/// The column types of a `DataTable`, along with dates which are returned as datetimes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CastType {
+    Str,
+    I64,
+    F64,
+    Bool,
+    Date,
+    Datetime,
+}
+
+fn cast_type_to_dtype(cast_type: &CastType) -> DataType {
+    match cast_type {
+        CastType::Str => DataType::Utf8,
+        CastType::I64 => DataType::Int64,
+        CastType::F64 => DataType::Float64,
+        CastType::Bool => DataType::Boolean,
+        CastType::Date => DataType::Date,
+        CastType::Datetime => DataType::Datetime(TimeUnit::Milliseconds, None),
+    }
+}
+
+/// Builds the expression converting a column from its current type. Values which can't be converted become null.
+fn cast_column_expr(column: &CastColumnConfig, from: &DataType) -> Expr {
+    let value = col(&column.property);
+    let target = cast_type_to_dtype(&column.to);
+    let expr = match (from, &column.to) {
+        (DataType::Utf8, CastType::Date | CastType::Datetime) => {
+            let options = StrptimeOptions { format: column.format.clone(), strict: false, exact: true, cache: true };
+            value.str().strptime(target, options)
+        },
+        (DataType::Utf8, CastType::Bool) => {
+            let matches_any = |texts: &[&str]| texts.iter().map(|x| value.clone().eq(lit(*x))).reduce(|a, b| a.or(b)).unwrap();
+            when(matches_any(&["true", "True", "TRUE", "1"])).then(lit(true))
+                .when(matches_any(&["false", "False", "FALSE", "0"])).then(lit(false))
+                .otherwise(lit(NULL).cast(DataType::Boolean))
+        },
+        (DataType::Date | DataType::Datetime(_, _), CastType::Str) if column.format.is_some() => {
+            value.dt().strftime(column.format.as_ref().unwrap())
+        },
+        _ => value.cast(target),
+    };
+    expr.alias(&column.property)
+}
+
+/// Builds the conversion expression for each column of a Cast pipe.
+fn build_cast_exprs(schema: &Schema, config: &CastPipeConfig) -> Result<Vec<Expr>, PipelineError> {
+    let mut exprs = Vec::new();
+    for (i, column) in config.columns.iter().enumerate() {
+        let from = match schema.get(&column.property) {
+            Some(x) => x,
+            None => {
+                let msg = format!("Column {} does not exist", column.property);
+                return Err(PipelineError::new(PipelineErrorKind::MissingColumn, msg).at(&["columns", i.to_string().as_str(), "property"]))
+            },
+        };
+        exprs.push(cast_column_expr(column, from));
+    }
+    Ok(exprs)
+}
+
+/// Converts the columns of a Cast pipe and collects the result, returning a frame over the converted data along with
+/// the number of non-null values in each column which became null when converted.
+fn cast_counting_failures(lf: LazyFrame, config: &CastPipeConfig, cast_exprs: Vec<Expr>) -> Result<(LazyFrame, Vec<(String, i64)>), PipelineError> {
+    let converted_names: Vec<String> = (0..cast_exprs.len()).map(|i| format!("__cast_{}", i)).collect();
+    let converted_exprs: Vec<Expr> = cast_exprs.into_iter().zip(converted_names.iter())
+        .map(|(expr, name)| expr.alias(name))
+        .collect();
+    let lf = collect_for_reuse(lf.with_columns(converted_exprs))?;
+    let counts: Vec<Expr> = config.columns.iter().zip(converted_names.iter()).map(|(column, name)| {
+        col(&column.property).is_not_null().and(col(name).is_null()).sum().cast(DataType::Int64).alias(&column.property)
+    }).collect();
+    let df = lf.clone().select(counts).collect()?;
+    let mut failures = Vec::new();
+    for column in config.columns.iter() {
+        let count = df.column(&column.property)?.i64()?.get(0).unwrap_or(0);
+        failures.push((column.property.clone(), count));
+    }
+    let replace_exprs: Vec<Expr> = config.columns.iter().zip(converted_names.iter())
+        .map(|(column, name)| col(name).alias(&column.property))
+        .collect();
+    Ok((lf.with_columns(replace_exprs).drop_columns(converted_names), failures))
+}
+
+/// Replaces missing values, with one rule per column.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillNullPipeConfig {
+    pipe_id: String,
+    rules: Vec<FillNullRule>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillNullRule {
+    property: String,
+    strategy: FillNullStrategy,
+    /// Value used by the Literal strategy
+    value: Option<FillNullValue>,
+    /// Column whose values are used by the Column strategy
+    column: Option<String>,
+    /// Columns to group by, so that filling only uses values from the same group
+    #[serde(default)]
+    over: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum FillNullStrategy {
+    Literal,
+    Column,
+    /// Uses the last non-null value before each null
+    Forward,
+    /// Uses the next non-null value after each null
+    Backward,
+    Mean,
+    Min,
+    Max,
+    /// Uses 0, false, an empty string or the epoch, depending on the column type
+    Zero,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FillNullValue {
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+/// Builds a literal of the given column type. Numbers are treated as epoch milliseconds for dates and datetimes.
+fn fill_null_value_expr(value: &FillNullValue, dtype: &DataType) -> Expr {
+    match (value, dtype) {
+        (FillNullValue::Number(x), DataType::Date | DataType::Datetime(_, _)) => lit(*x as i64).cast(DataType::Datetime(TimeUnit::Milliseconds, None)).cast(dtype.clone()),
+        (FillNullValue::Number(x), _) => lit(*x).cast(dtype.clone()),
+        (FillNullValue::Bool(x), _) => lit(*x).cast(dtype.clone()),
+        (FillNullValue::Str(x), _) => lit(x.as_str()).cast(dtype.clone()),
+    }
+}
+
+fn fill_null_rule_expr(rule: &FillNullRule, schema: &Schema) -> Result<Expr, PipelineError> {
+    let dtype = match schema.get(&rule.property) {
+        Some(x) => x,
+        None => return Err(PipelineError::new(PipelineErrorKind::MissingColumn, format!("Column {} does not exist", rule.property)).at(&["property"])),
+    };
+    let value = col(&rule.property);
+    let expr = match rule.strategy {
+        FillNullStrategy::Literal => match &rule.value {
+            Some(x) => value.fill_null(fill_null_value_expr(x, dtype)),
+            None => return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "Literal strategy requires a value").at(&["value"])),
+        },
+        FillNullStrategy::Column => match &rule.column {
+            Some(x) => value.fill_null(col(x).cast(dtype.clone())),
+            None => return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "Column strategy requires a column").at(&["column"])),
+        },
+        FillNullStrategy::Forward => value.forward_fill(None),
+        FillNullStrategy::Backward => value.backward_fill(None),
+        FillNullStrategy::Mean => match dtype {
+            DataType::Int64 | DataType::Float64 => value.clone().fill_null(value.mean().cast(dtype.clone())),
+            _ => return Err(PipelineError::new(PipelineErrorKind::TypeMismatch, format!("Cannot take the mean of column {} of type {}", rule.property, dtype)).at(&["strategy"])),
+        },
+        FillNullStrategy::Min => value.clone().fill_null(value.min()),
+        FillNullStrategy::Max => value.clone().fill_null(value.max()),
+        FillNullStrategy::Zero => {
+            let zero = match dtype {
+                DataType::Utf8 => lit(""),
+                DataType::Boolean => lit(false),
+                DataType::Int64 | DataType::Float64 => lit(0).cast(dtype.clone()),
+                DataType::Date | DataType::Datetime(_, _) => lit(0i64).cast(DataType::Datetime(TimeUnit::Milliseconds, None)).cast(dtype.clone()),
+                _ => return Err(PipelineError::new(PipelineErrorKind::UnsupportedDtype, format!("Column {} has unsupported type {}", rule.property, dtype)).at(&["strategy"])),
+            };
+            value.fill_null(zero)
+        },
+    };
+    match rule.over.len() {
+        0 => Ok(expr.alias(&rule.property)),
+        _ => Ok(expr.over(rule.over.iter().map(|x| col(x)).collect::<Vec<_>>()).alias(&rule.property)),
+    }
+}
+
+/// Keeps a random subset of rows, given either as a number of rows or a fraction of the input.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplePipeConfig {
+    pipe_id: String,
+    n: Option<usize>,
+    fraction: Option<f64>,
+    #[serde(default)]
+    with_replacement: bool,
+    /// Seed for the random number generator, so the same rows are returned on every run. If not given, a new
+    /// sample is taken each run.
+    seed: Option<u64>,
+}
+
+fn sample_size_error(config: &SamplePipeConfig) -> Option<PipelineError> {
+    match (config.n, config.fraction) {
+        (Some(_), Some(_)) | (None, None) => Some(PipelineError::new(PipelineErrorKind::InvalidInput, "Exactly one of n or fraction must be given")),
+        (None, Some(fraction)) if fraction < 0.0 || (fraction > 1.0 && !config.with_replacement) => {
+            let msg = format!("Fraction must be between 0 and 1 when sampling without replacement ({} found)", fraction);
+            Some(PipelineError::new(PipelineErrorKind::InvalidInput, msg).at(&["fraction"]))
+        },
+        _ => None,
+    }
+}
+
+/// Turns list columns into one row per list element. Lists exploded together must have equal lengths in each row.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplodePipeConfig {
+    pipe_id: String,
+    properties: Vec<String>,
+}
+
+/// Keeps the first `n` rows of each group when ordered by `order_by`. Output rows are in that order.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopNPerGroupPipeConfig {
+    pipe_id: String,
+    group_by: Vec<String>,
+    order_by: Vec<SortKeyConfig>,
+    n: IdxSize,
+    #[serde(default)]
+    ties: TopNTies,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum TopNTies {
+    /// Keeps exactly `n` rows per group, taking rows in input order among ties
+    #[default]
+    First,
+    /// Also keeps every row tied with the last of the `n` rows, so a group may return more than `n` rows
+    WithTies,
+}
+
+/// Joins each left row to every right row whose interval from `start` to `end` contains the left `value`, with equal
+/// `by` columns. A left row in several intervals is returned once per interval. Output rows are ordered by `value`,
+/// then by the order of the left and right rows.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntervalJoinPipeConfig {
+    left_pipe_id: String,
+    right_pipe_id: String,
+    value: String,
+    start: String,
+    end: String,
+    #[serde(default)]
+    by: Vec<String>,
+    #[serde(default)]
+    closed: IntervalClosed,
+    #[serde(default)]
+    how: IntervalJoinType,
+    /// Added to the names of right columns which collide with left columns. Defaults to "_right".
+    suffix: Option<String>,
+}
+
+/// Which ends of an interval are inclusive.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum IntervalClosed {
+    #[default]
+    Both,
+    Left,
+    Right,
+    None,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum IntervalJoinType {
+    /// Keeps left rows with no matching interval, with null right columns
+    #[default]
+    Left,
+    Inner,
+}
+
+/// Joins rows to intervals by pairing each left row with every right interval containing its value, then joining the
+/// full left and right rows back on their row numbers.
+fn interval_join(left_lf: LazyFrame, right_lf: LazyFrame, config: &IntervalJoinPipeConfig) -> LazyFrame {
+    let (left_row_column, right_row_column) = ("__interval_left_row", "__interval_right_row");
+    let (value_column, start_column, end_column) = ("__interval_value", "__interval_start", "__interval_end");
+    // Both sides are read twice, once to find the matches and once for the output columns
+    let left_lf = left_lf.with_row_count(left_row_column, None).cache();
+    let right_lf = right_lf.with_row_count(right_row_column, None).cache();
+
+    let mut value_exprs: Vec<Expr> = config.by.iter().map(|x| col(x)).collect();
+    value_exprs.extend([col(&config.value).alias(value_column), col(left_row_column)]);
+    let values = left_lf.clone().select(value_exprs);
+    let mut bound_exprs: Vec<Expr> = config.by.iter().map(|x| col(x)).collect();
+    bound_exprs.extend([col(&config.start).alias(start_column), col(&config.end).alias(end_column), col(right_row_column)]);
+    let bounds = right_lf.clone().select(bound_exprs);
+    let candidates = match config.by.len() {
+        0 => values.cross_join(bounds),
+        _ => {
+            let by: Vec<Expr> = config.by.iter().map(|x| col(x)).collect();
+            JoinBuilder::new(values).with(bounds).how(JoinType::Inner).left_on(by.clone()).right_on(by).finish()
+        },
+    };
+    let value = col(value_column);
+    let after_start = match config.closed {
+        IntervalClosed::Both | IntervalClosed::Left => value.clone().gt_eq(col(start_column)),
+        IntervalClosed::Right | IntervalClosed::None => value.clone().gt(col(start_column)),
+    };
+    let before_end = match config.closed {
+        IntervalClosed::Both | IntervalClosed::Right => value.lt_eq(col(end_column)),
+        IntervalClosed::Left | IntervalClosed::None => value.lt(col(end_column)),
+    };
+    let matches = candidates
+        .filter(after_start.and(before_end))
+        .select([col(left_row_column), col(right_row_column)]);
+
+    let how = match config.how {
+        IntervalJoinType::Left => JoinType::Left,
+        IntervalJoinType::Inner => JoinType::Inner,
+    };
+    let matched = JoinBuilder::new(left_lf)
+        .with(matches)
+        .how(how)
+        .left_on([col(left_row_column)])
+        .right_on([col(left_row_column)])
+        .finish();
+    // The right `by` columns equal the left ones for every match
+    let mut jb = JoinBuilder::new(matched)
+        .with(right_lf.drop_columns(config.by.clone()))
+        .how(JoinType::Left)
+        .left_on([col(right_row_column)])
+        .right_on([col(right_row_column)]);
+    if let Some(suffix) = &config.suffix {
+        jb = jb.suffix(suffix);
+    }
+    jb.finish()
+        .sort_by_exprs([col(&config.value), col(left_row_column), col(right_row_column)], [false, false, false], false, true)
+        .drop_columns([left_row_column, right_row_column])
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StringToDatePipeConfig {
+    pipe_id: String,
+    column_from: String,
+    column_to: String,
+    format: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag="type")]
+pub enum PipeConfig {
+    Source(SourcePipeConfig),
+    DerivedValues(DerivedValuesPipeConfig),
+    GroupAndReduce(GroupAndReducePipeConfig),
+    Filter(FilterPipeConfig),
+    Join(JoinPipeConfig),
+    Rename(RenamePipeConfig),
+    Sort(SortPipeConfig),
+    Slice(SlicePipeConfig),
+    Select(SelectPipeConfig),
+    Drop(DropPipeConfig),
+    Union(UnionPipeConfig),
+    Pivot(PivotPipeConfig),
+    Unpivot(UnpivotPipeConfig),
+    Distinct(DistinctPipeConfig),
+    Cast(CastPipeConfig),
+    StringToDate(StringToDatePipeConfig),
+    IntervalJoin(IntervalJoinPipeConfig),
+    TopNPerGroup(TopNPerGroupPipeConfig),
+    Explode(ExplodePipeConfig),
+    Sample(SamplePipeConfig),
+    FillNull(FillNullPipeConfig),
+}
+
+pub struct LazyFrameFactory {
+    lazy_frames: HashMap<String, LazyFrame>,
+    pipe_configs: HashMap<String, PipeConfig>,
+    /// Pipes whose output is used more than once, and how their reuse is handled.
+    shared_pipes: HashMap<String, SharedPipeStrategy>,
+    /// LazyFrames already built for each pipe id during this run.
+    built_frames: RefCell<HashMap<String, LazyFrame>>,
+    /// Conversion failures per column for each Cast pipe with `countFailures` built during this run.
+    cast_failures: RefCell<HashMap<String, HashMap<String, i64>>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum SharedPipeStrategy {
+    /// Wrap the plan in `LazyFrame::cache()` so polars computes it once per collect.
+    Cache,
+    /// Collect the pipe once and reuse the resulting DataFrame. Only used for pipes shared between endpoints,
+    /// as each endpoint's plan is run separately and a cache is not shared between them.
+    Materialize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DataTable {
+    f64: HashMap<String, Vec<Option<f64>>>,
+    i64: HashMap<String, Vec<Option<i64>>>,
+    str: HashMap<String, Vec<Option<String>>>,
+    datetime: HashMap<String, Vec<Option<i64>>>,
+    bool: HashMap<String, Vec<Option<bool>>>,
+    /// List columns, with one list of values per row
+    #[serde(default)]
+    list_f64: HashMap<String, Vec<Option<Vec<Option<f64>>>>>,
+    #[serde(default)]
+    list_i64: HashMap<String, Vec<Option<Vec<Option<i64>>>>>,
+    #[serde(default)]
+    list_str: HashMap<String, Vec<Option<Vec<Option<String>>>>>,
+    #[serde(default)]
+    list_bool: HashMap<String, Vec<Option<Vec<Option<bool>>>>>,
+    /// Order of the columns. On input, columns not listed follow the listed ones in name order.
+    #[serde(rename = "columnOrder", default)]
+    column_order: Vec<String>,
+    /// For each upstream Cast pipe with `countFailures`, the number of values per column which could not be converted.
+    /// Ignored on input.
+    #[serde(rename = "castFailures", default)]
+    cast_failures: HashMap<String, HashMap<String, i64>>,
+}
+
+/// Builds a list series from one list of values per row.
+fn list_series<T>(name: &str, rows: &Vec<Option<Vec<Option<T>>>>, inner: DataType) -> Result<Series, PipelineError>
+    where Series: NamedFrom<Vec<Option<T>>, [Option<T>]>, T: Clone {
+    let rows: Vec<Option<Series>> = rows.iter().map(|row| row.as_ref().map(|values| Series::new("", values.clone()))).collect();
+    // Casting also sets the inner type when every row is null
+    Ok(Series::new(name, rows).cast(&DataType::List(Box::new(inner)))?)
+}
+
+/// Returns one list of values per row of a list series, converting each list with `values`.
+fn list_values<T>(column: &Series, values: impl Fn(&Series) -> PolarsResult<Vec<Option<T>>>) -> Result<Vec<Option<Vec<Option<T>>>>, PipelineError> {
+    let mut rows = Vec::new();
+    for row in column.list()?.into_iter() {
+        rows.push(match row {
+            Some(x) => Some(values(&x)?),
+            None => None,
+        });
+    }
+    Ok(rows)
+}
+
+fn data_table_to_frame(table: &DataTable) -> Result<LazyFrame, PipelineError> {
+    let mut series_vec: Vec<Series> = Vec::new();
+    table.f64.iter().for_each(|(name, values)| {
+        series_vec.push(Series::new(name, values))
+    });
+    table.i64.iter().for_each(|(name, values)| {
+        series_vec.push(Series::new(name, values))
+    });
+    table.str.iter().for_each(|(name, values)| {
+        series_vec.push(Series::new(name, values))
+    });
+    table.bool.iter().for_each(|(name, values)| {
+        series_vec.push(Series::new(name, values))
+    });
+    for (name, values) in table.datetime.iter() {
+        // Datetimes are passed as milliseconds since the epoch
+        series_vec.push(Series::new(name, values).cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?);
+    }
+    for (name, rows) in table.list_f64.iter() {
+        series_vec.push(list_series(name, rows, DataType::Float64)?);
+    }
+    for (name, rows) in table.list_i64.iter() {
+        series_vec.push(list_series(name, rows, DataType::Int64)?);
+    }
+    for (name, rows) in table.list_str.iter() {
+        series_vec.push(list_series(name, rows, DataType::Utf8)?);
+    }
+    for (name, rows) in table.list_bool.iter() {
+        series_vec.push(list_series(name, rows, DataType::Boolean)?);
+    }
+    let df = DataFrame::new(series_vec)?;
+    for (i, name) in table.column_order.iter().enumerate() {
+        if df.column(name).is_err() {
+            let msg = format!("Column {} in columnOrder does not exist", name);
+            return Err(PipelineError::new(PipelineErrorKind::MissingColumn, msg).at(&["columnOrder", i.to_string().as_str()]))
+        }
+    }
+    let mut unlisted: Vec<&str> = df.get_column_names().into_iter().filter(|x| !table.column_order.iter().any(|y| y == x)).collect();
+    unlisted.sort();
+    let names: Vec<&str> = table.column_order.iter().map(|x| x.as_str()).chain(unlisted).collect();
+    Ok(df.select(names)?.lazy())
+}
+
+/// Column names of a table grouped by their `DataTable` type, without any values.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct TableSchema {
+    f64: Vec<String>,
+    i64: Vec<String>,
+    str: Vec<String>,
+    datetime: Vec<String>,
+    bool: Vec<String>,
+    list_f64: Vec<String>,
+    list_i64: Vec<String>,
+    list_str: Vec<String>,
+    list_bool: Vec<String>,
+}
+
+fn table_schema_to_frame(table_schema: &TableSchema) -> Result<LazyFrame, PipelineError> {
+    let columns = [
+        (&table_schema.f64, DataType::Float64),
+        (&table_schema.i64, DataType::Int64),
+        (&table_schema.str, DataType::Utf8),
+        (&table_schema.datetime, DataType::Datetime(TimeUnit::Milliseconds, None)),
+        (&table_schema.bool, DataType::Boolean),
+        (&table_schema.list_f64, DataType::List(Box::new(DataType::Float64))),
+        (&table_schema.list_i64, DataType::List(Box::new(DataType::Int64))),
+        (&table_schema.list_str, DataType::List(Box::new(DataType::Utf8))),
+        (&table_schema.list_bool, DataType::List(Box::new(DataType::Boolean))),
+    ];
+    let series_vec: Vec<Series> = columns.iter()
+        .flat_map(|(names, dtype)| names.iter().map(move |name| Series::new_empty(name, dtype)))
+        .collect();
+    Ok(DataFrame::new(series_vec)?.lazy())
+}
+
+fn schema_to_table_schema(schema: &Schema) -> Result<TableSchema, PipelineError> {
+    let mut table_schema = TableSchema::default();
+    for (name, dtype) in schema.iter() {
+        match dtype {
+            DataType::Float64 => table_schema.f64.push(name.to_string()),
+            DataType::Int64 => table_schema.i64.push(name.to_string()),
+            DataType::Utf8 => table_schema.str.push(name.to_string()),
+            DataType::Boolean => table_schema.bool.push(name.to_string()),
+            DataType::Date | DataType::Datetime(_, _) => table_schema.datetime.push(name.to_string()),
+            DataType::List(inner) if **inner == DataType::Float64 => table_schema.list_f64.push(name.to_string()),
+            DataType::List(inner) if **inner == DataType::Int64 => table_schema.list_i64.push(name.to_string()),
+            DataType::List(inner) if **inner == DataType::Utf8 => table_schema.list_str.push(name.to_string()),
+            DataType::List(inner) if **inner == DataType::Boolean => table_schema.list_bool.push(name.to_string()),
+            dtype => {
+                let msg = format!("Column {} has dtype {} which cannot be returned in a DataTable", name, dtype);
+                return Err(PipelineError::new(PipelineErrorKind::UnsupportedDtype, msg))
+            },
+        }
+    }
+    Ok(table_schema)
+}
+
+fn data_frame_to_table(frame: DataFrame) -> Result<DataTable, PipelineError> {
+    let mut data_table = DataTable {
+        f64: HashMap::new(),
+        i64: HashMap::new(),
+        str: HashMap::new(),
+        datetime: HashMap::new(),
+        bool: HashMap::new(),
+        list_f64: HashMap::new(),
+        list_i64: HashMap::new(),
+        list_str: HashMap::new(),
+        list_bool: HashMap::new(),
+        column_order: frame.get_column_names().into_iter().map(|x| x.to_string()).collect(),
+        cast_failures: HashMap::new(),
+    };
+
+    for column in frame.iter() {
+        match column.dtype() {
+            DataType::Float64 => {
+                let values = column.f64()?.into_iter().collect();
+                data_table.f64.insert(column.name().into(), values);
+            },
+            DataType::Int64 => {
+                let values = column.i64()?.into_iter().collect();
+                data_table.i64.insert(column.name().into(), values);
+            },
+            DataType::Utf8 => {
+                let values = column.utf8()?.into_iter().map(|x| match x { None => None, Some(y) => Some(y.to_string())}).collect();
+                data_table.str.insert(column.name().into(), values);
+            },
+            DataType::Boolean => {
+                let values = column.bool()?.into_iter().collect();
+                data_table.bool.insert(column.name().into(), values);
+            },
+            DataType::Date | DataType::Datetime(_, _) => {
+                let millis = column.cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?.cast(&DataType::Int64)?;
+                let values = millis.i64()?.into_iter().collect();
+                data_table.datetime.insert(column.name().into(), values);
+            },
+            DataType::List(inner) if **inner == DataType::Float64 => {
+                let values = list_values(column, |x| Ok(x.f64()?.into_iter().collect()))?;
+                data_table.list_f64.insert(column.name().into(), values);
+            },
+            DataType::List(inner) if **inner == DataType::Int64 => {
+                let values = list_values(column, |x| Ok(x.i64()?.into_iter().collect()))?;
+                data_table.list_i64.insert(column.name().into(), values);
+            },
+            DataType::List(inner) if **inner == DataType::Utf8 => {
+                let values = list_values(column, |x| Ok(x.utf8()?.into_iter().map(|y| y.map(|z| z.to_string())).collect()))?;
+                data_table.list_str.insert(column.name().into(), values);
+            },
+            DataType::List(inner) if **inner == DataType::Boolean => {
+                let values = list_values(column, |x| Ok(x.bool()?.into_iter().collect()))?;
+                data_table.list_bool.insert(column.name().into(), values);
+            },
+            dtype => {
+                let msg = format!("Column {} has dtype {} which cannot be returned in a DataTable", column.name(), dtype);
+                return Err(PipelineError::new(PipelineErrorKind::UnsupportedDtype, msg))
+            },
+        }
+    };
+    Ok(data_table)
+}
+
+#[wasm_bindgen]
 pub fn runDataPipeline(pipe_ids: JsValue, input_data: JsValue, configs: JsValue) -> Result<JsValue, JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
 
     log("start run_data_pipeline");
     let pipe_configs: HashMap<String, PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
         Ok(x) => x,
-        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(e.into()) }
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) }
     };
     let pipes: Vec<String> = match serde_wasm_bindgen::from_value(pipe_ids) {
         Ok(x) => x,
-        Err(e) => { log(&format!("Error parsing pipe_ids: {:?}", e)); return Err(e.into()) }
+        Err(e) => { log(&format!("Error parsing pipe_ids: {:?}", e)); return Err(PipelineError::from(e).at(&["pipeIds"]).into()) }
     };
     let inputs: HashMap<String, DataTable> = match serde_wasm_bindgen::from_value(input_data) {
         Ok(x) => x,
-        Err(e) => { log(&format!("Error parsing input_data: {:?}", e)); return Err(e.into()) }
+        Err(e) => { log(&format!("Error parsing input_data: {:?}", e)); return Err(PipelineError::from(e).at(&["inputData"]).into()) }
+    };
+    let result = match run_data_pipeline(pipes, inputs, pipe_configs) {
+        Ok(x) => x,
+        Err(e) => { log(&format!("Error running pipeline: {}", e)); return Err(e.into()) }
     };
-    let result = run_data_pipeline(pipes, inputs, pipe_configs);
-    Ok(serde_wasm_bindgen::to_value(&result).unwrap())
+    match serde_wasm_bindgen::to_value(&result) {
+        Ok(x) => Ok(x),
+        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); Err(PipelineError::from(e).into()) }
+    }
 }
 
 
-fn run_data_pipeline(pipe_ids: Vec<String>, inputs: HashMap<String, DataTable>, pipe_configs: HashMap<String, PipeConfig>) -> DataTable {
+fn run_data_pipeline(pipe_ids: Vec<String>, inputs: HashMap<String, DataTable>, pipe_configs: HashMap<String, PipeConfig>) -> Result<HashMap<String, DataTable>, PipelineError> {
+    let lazy_inputs = match data_tables_to_frames(&inputs) {
+        Ok(x) => x,
+        Err(e) => return Err(e),
+    };
+    log("lazy_inputs ready");
+
+    if let Err(e) = check_for_cycles(&pipe_ids, &pipe_configs) {
+        return Err(e)
+    }
+    let shared_pipes = get_shared_pipes(&pipe_ids, &pipe_configs);
+    let lff = LazyFrameFactory::new(pipe_configs, lazy_inputs, shared_pipes);
+    lff.create_data_tables(&pipe_ids)
+}
+
+fn data_tables_to_frames(inputs: &HashMap<String, DataTable>) -> Result<HashMap<String, LazyFrame>, PipelineError> {
     let mut lazy_inputs: HashMap<String, LazyFrame> = HashMap::new();
     for (key, value) in inputs.iter() {
-        lazy_inputs.insert(key.to_string(), data_table_to_frame(value));
+        let lf = match data_table_to_frame(value) {
+            Ok(x) => x,
+            Err(e) => return Err(PipelineError { message: format!("Invalid input data for source {}: {}", key, e.message), ..e }.at(&["inputData", key.as_str()])),
+        };
+        lazy_inputs.insert(key.to_string(), lf);
     }
-    log("lazy_inputs ready");
+    Ok(lazy_inputs)
+}
 
-    let lff = LazyFrameFactory {
-        pipe_configs,
-        lazy_frames: lazy_inputs,
+/// Returns the logical plan polars will run for a pipe, as built by `runDataPipeline` when it is the only endpoint.
+/// Building the plan runs any upstream pipes whose data is needed to plan the pipes after them, such as the inputs
+/// of Cross joins, validated joins, strict or counted Casts and Pivots without `pivotValues`, so this can be as
+/// costly as running the pipeline. Those pipes appear in the plan as in-memory DataFrames.
+#[wasm_bindgen]
+pub fn describePipePlan(pipe_id: String, input_data: JsValue, configs: JsValue) -> Result<String, JsValue> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+
+    let pipe_configs: HashMap<String, PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
+        Ok(x) => x,
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) }
     };
-    lff.create_lazy_frame(pipe_ids.iter().next().unwrap()).unwrap()
+    let inputs: HashMap<String, DataTable> = match serde_wasm_bindgen::from_value(input_data) {
+        Ok(x) => x,
+        Err(e) => { log(&format!("Error parsing input_data: {:?}", e)); return Err(PipelineError::from(e).at(&["inputData"]).into()) }
+    };
+    match describe_pipe_plan(&pipe_id, inputs, pipe_configs) {
+        Ok(x) => Ok(x),
+        Err(e) => { log(&format!("Error describing plan: {}", e)); Err(e.into()) }
+    }
+}
+
+fn describe_pipe_plan(pipe_id: &String, inputs: HashMap<String, DataTable>, pipe_configs: HashMap<String, PipeConfig>) -> Result<String, PipelineError> {
+    let lazy_inputs = match data_tables_to_frames(&inputs) {
+        Ok(x) => x,
+        Err(e) => return Err(e),
+    };
+    let pipe_ids = vec![pipe_id.clone()];
+    if let Err(e) = check_for_cycles(&pipe_ids, &pipe_configs) {
+        return Err(e)
+    }
+    let shared_pipes = get_shared_pipes(&pipe_ids, &pipe_configs);
+    let lff = LazyFrameFactory::new(pipe_configs, lazy_inputs, shared_pipes);
+    let lf = lff.get_lazy_frame(pipe_id)?;
+    Ok(lf.describe_plan())
+}
+
+/// Returns the pipes whose output is used more than once when evaluating the given endpoints, either by
+/// several downstream pipes or by several endpoints.
+fn get_shared_pipes(pipe_ids: &Vec<String>, pipe_configs: &HashMap<String, PipeConfig>) -> HashMap<String, SharedPipeStrategy> {
+    let mut endpoint_count: HashMap<String, usize> = HashMap::new();
+    for pipe_id in pipe_ids.iter().collect::<HashSet<_>>() {
+        for reached in get_upstream_pipes(pipe_id, pipe_configs) {
+            *endpoint_count.entry(reached).or_insert(0) += 1;
+        }
+    }
+    let mut consumer_count: HashMap<String, usize> = HashMap::new();
+    for pipe_id in endpoint_count.keys() {
+        if let Some(config) = pipe_configs.get(pipe_id) {
+            for upstream_id in get_source_pipes_for_single(config) {
+                *consumer_count.entry(upstream_id).or_insert(0) += 1;
+            }
+        }
+    }
+    let shared: HashSet<String> = endpoint_count.keys()
+        .filter(|pipe_id| endpoint_count[*pipe_id] > 1 || consumer_count.get(*pipe_id).copied().unwrap_or(0) > 1)
+        .cloned()
+        .collect();
+
+    shared.into_iter().map(|pipe_id| {
+        let strategy = match endpoint_count[&pipe_id] {
+            1 => SharedPipeStrategy::Cache,
+            _ => SharedPipeStrategy::Materialize,
+        };
+        (pipe_id, strategy)
+    }).collect()
+}
+
+/// Returns an error naming the full cycle path (e.g. `a -> b -> c -> a`) if any pipe upstream of the given
+/// pipes depends on itself.
+fn check_for_cycles(pipe_ids: &Vec<String>, pipe_configs: &HashMap<String, PipeConfig>) -> Result<(), PipelineError> {
+    match find_cycles(pipe_ids, pipe_configs).first() {
+        Some(cycle) => Err(cycle_error(cycle)),
+        None => Ok(()),
+    }
+}
+
+fn cycle_error(cycle: &Vec<String>) -> PipelineError {
+    let msg = format!("Cycle found in pipe configs: {}", cycle.join(" -> "));
+    PipelineError::new(PipelineErrorKind::Cycle, msg).in_pipe(&cycle[0])
+}
+
+/// Returns every cycle upstream of the given pipes, each as the list of pipe ids in the cycle with the first
+/// pipe repeated at the end.
+fn find_cycles(pipe_ids: &Vec<String>, pipe_configs: &HashMap<String, PipeConfig>) -> Vec<Vec<String>> {
+    let mut finished = HashSet::new();
+    let mut path = Vec::new();
+    let mut cycles = Vec::new();
+    for pipe_id in pipe_ids.iter() {
+        find_cycles_recursive(pipe_id, pipe_configs, &mut finished, &mut path, &mut cycles);
+    }
+    cycles
+}
+
+/// Depth-first search for cycles. `path` holds the pipes currently being traversed and `finished` the pipes
+/// whose upstream graph has already been searched.
+fn find_cycles_recursive(pipe_id: &String, pipe_configs: &HashMap<String, PipeConfig>, finished: &mut HashSet<String>, path: &mut Vec<String>, cycles: &mut Vec<Vec<String>>) {
+    if let Some(position) = path.iter().position(|x| x == pipe_id) {
+        let mut cycle = path[position..].to_vec();
+        cycle.push(pipe_id.clone());
+        cycles.push(cycle);
+        return
+    }
+    if finished.contains(pipe_id) {
+        return
+    }
+    path.push(pipe_id.clone());
+    if let Some(config) = pipe_configs.get(pipe_id) {
+        for upstream_id in get_source_pipes_for_single(config) {
+            find_cycles_recursive(&upstream_id, pipe_configs, finished, path, cycles);
+        }
+    }
+    path.pop();
+    finished.insert(pipe_id.clone());
+}
+
+/// Returns the given pipe and every pipe upstream of it. Unknown pipe ids are included but not followed.
+fn get_upstream_pipes(pipe_id: &String, pipe_configs: &HashMap<String, PipeConfig>) -> HashSet<String> {
+    let mut reachable = HashSet::new();
+    let mut stack = vec![pipe_id.clone()];
+    while let Some(current) = stack.pop() {
+        if !reachable.insert(current.clone()) {
+            continue
+        }
+        if let Some(config) = pipe_configs.get(&current) {
+            stack.extend(get_source_pipes_for_single(config));
+        }
+    }
+    reachable
 }
 
 impl LazyFrameFactory {
-    pub fn create_lazy_frame(self: &Self, pipe_id: &String) -> Result<DataTable, String> {
-        log("Start create_lazy_frame");
-        let result_lf = match self.pipe_configs.get(pipe_id) {
-            Some(config) => self.recurse(&config),
-            None => Err(format!("No pipe with id {} found in pipe_configs", pipe_id)),
-        }.unwrap();
-        log("Obtained result_lf");
-        let result_table = match data_frame_to_table(result_lf) {
+    fn new(pipe_configs: HashMap<String, PipeConfig>, lazy_frames: HashMap<String, LazyFrame>, shared_pipes: HashMap<String, SharedPipeStrategy>) -> Self {
+        LazyFrameFactory {
+            lazy_frames,
+            pipe_configs,
+            shared_pipes,
+            built_frames: RefCell::new(HashMap::new()),
+            cast_failures: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Builds every requested pipe from this factory and collects them together.
+    pub fn create_data_tables(self: &Self, pipe_ids: &Vec<String>) -> Result<HashMap<String, DataTable>, PipelineError> {
+        log("Start create_data_tables");
+        let lfs = pipe_ids.iter()
+            .map(|pipe_id| self.get_lazy_frame(pipe_id))
+            .collect::<Result<Vec<LazyFrame>, PipelineError>>()?;
+        log("Obtained result lazy frames");
+        let frames = match collect_all(lfs) {
+            Ok(x) => x,
+            Err(e) => { log(&format!("{:?}", e)); return Err(e.into()) },
+        };
+        let mut result_tables = HashMap::new();
+        for (pipe_id, frame) in pipe_ids.iter().zip(frames.into_iter()) {
+            let mut table = match data_frame_to_table(frame) {
+                Ok(x) => x,
+                Err(e) => { log(&format!("Error converting lazyframe to table formats {:?}", e)); return Err(e.in_pipe(pipe_id)) },
+            };
+            let upstream_pipes = get_upstream_pipes(pipe_id, &self.pipe_configs);
+            table.cast_failures = self.cast_failures.borrow().iter()
+                .filter(|(cast_pipe_id, _)| upstream_pipes.contains(*cast_pipe_id))
+                .map(|(cast_pipe_id, counts)| (cast_pipe_id.clone(), counts.clone()))
+                .collect();
+            result_tables.insert(pipe_id.clone(), table);
+        }
+        log("End create_data_tables");
+        Ok(result_tables)
+    }
+
+    /// Looks up a pipe by id and builds its LazyFrame. Each pipe is built at most once per run, and pipes
+    /// used more than once are cached or collected according to their `SharedPipeStrategy`.
+    fn get_lazy_frame(self: &Self, pipe_id: &String) -> Result<LazyFrame, PipelineError> {
+        if let Some(lf) = self.built_frames.borrow().get(pipe_id) {
+            return Ok(lf.clone())
+        }
+        let config = match self.pipe_configs.get(pipe_id) {
+            Some(c) => c,
+            None => {
+                log(&format!("Pipe id {} not found", pipe_id));
+                return Err(PipelineError::new(PipelineErrorKind::MissingPipe, format!("Pipe id {} not found", pipe_id)).in_pipe(pipe_id))
+            },
+        };
+        let lf = match self.recurse(pipe_id, config) {
             Ok(x) => x,
-            Err(e) => { log(&format!("Error converting lazyframe to table formats {:?}", e)); return Err(e.into()) },
+            Err(e) => return Err(e.in_pipe(pipe_id)),
+        };
+        let built_lf = match self.shared_pipes.get(pipe_id) {
+            None => lf,
+            Some(SharedPipeStrategy::Cache) => lf.cache(),
+            Some(SharedPipeStrategy::Materialize) => match lf.collect() {
+                Ok(df) => df.lazy(),
+                Err(e) => { log(&format!("{:?}", e)); return Err(PipelineError::from(e).in_pipe(pipe_id)) },
+            },
         };
-        log("End create_lazy_frame");
-        return Ok(result_table)
+        self.built_frames.borrow_mut().insert(pipe_id.clone(), built_lf.clone());
+        Ok(built_lf)
     }
 
-    fn recurse(self: &Self, c: &PipeConfig) -> Result<LazyFrame, String> {
+    /// Builds the LazyFrame for a pipe referenced at `path` in the config of the pipe currently being built.
+    fn get_upstream_lazy_frame<S: AsRef<str>>(self: &Self, pipe_id: &String, path: &[S]) -> Result<LazyFrame, PipelineError> {
+        if !self.pipe_configs.contains_key(pipe_id) {
+            log(&format!("Pipe id {} not found", pipe_id));
+            return Err(PipelineError::new(PipelineErrorKind::MissingPipe, format!("Pipe id {} not found", pipe_id)).at(path))
+        }
+        self.get_lazy_frame(pipe_id)
+    }
+
+    fn recurse(self: &Self, pipe_id: &String, c: &PipeConfig) -> Result<LazyFrame, PipelineError> {
         log("Start recurse");
         match c {
             PipeConfig::Source(config) => {
@@ -465,195 +2062,526 @@ impl LazyFrameFactory {
                     None => {
                         let msg = format!("No data source with id {:?} was found in inputs array.", config.source_id);
                         log(&msg);
-                        return Err(msg)
+                        return Err(PipelineError::new(PipelineErrorKind::MissingSource, msg).at(&["sourceId"]))
                     },
                 }.clone();
                 log("SourceCsv data_lf successful");
                 Ok(data_lf)
             },
-            PipeConfig::DerivedValues(config) => {
-                let upstream_config = match self.pipe_configs.get(&config.pipe_id) {
-                    Some(c) => c.clone(),
-                    None => { println!("Pipe id {} not found", config.pipe_id); return Err(format!("Pipe id {} not found", config.pipe_id)) },
-                };
-                let lf = match self.recurse(&upstream_config) {
+            PipeConfig::DerivedValues(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let pl_exprs_vec: Vec<Expr> = match config.calcs.iter().enumerate().map(|(i, calc)| {
+                    match recurse_derived_expression(calc.expression.clone()) {
+                        Ok(y) => Ok(y.alias(&calc.name)),
+                        Err(e) => Err(e.at(&["calcs", i.to_string().as_str(), "expression"])),
+                    }
+                }).collect::<Result<Vec<Expr>, PipelineError>>() {
+                    Ok(x) => x,
+                    Err(e) => return Err(e),
+                };
+                let final_lf = pl_exprs_vec.iter().fold(lf.clone(), |acc_lf, expr| acc_lf.with_column(expr.clone()) );
+                Ok(final_lf)
+            },
+            PipeConfig::GroupAndReduce(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let groupby = match config.group_by.len() {
+                    0 => { return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by zero variables").at(&["groupBy"])) },
+                    1 => lf.groupby([ col(&config.group_by[0]) ]),
+                    2 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]) ]),
+                    3 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]) ]),
+                    4 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]) ]),
+                    5 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]), col(&config.group_by[4]) ]),
+                    6 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]), col(&config.group_by[4]), col(&config.group_by[5]) ]),
+                    _ => { return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by more than 6 variables").at(&["groupBy"])) },
+                };
+                let lf_out = groupby.agg(
+                    config.clone().aggs.into_iter().map(|c| {
+                        match c.r#type {
+                            AggType::Sum => {
+                                col(&c.agg_property).sum().alias(&c.name)
+                            },
+                            AggType::Max => {
+                                col(&c.agg_property).max().alias(&c.name)
+                            },
+                            AggType::Min => {
+                                col(&c.agg_property).min().alias(&c.name)
+                            },
+                            AggType::List => {
+                                col(&c.agg_property).alias(&c.name)
+                            },
+                        }
+                    }).collect::<Vec<_>>()
+                );
+                Ok(lf_out)
+            },
+            PipeConfig::Filter(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let pl_exprs_vec: Vec<Expr> = match config.filters.iter().enumerate().map(|(i, calc)| {
+                    match recurse_derived_expression(calc.clone()) {
+                        Ok(y) => Ok(y),
+                        Err(e) => Err(e.at(&["filters", i.to_string().as_str()])),
+                    }
+                }).collect::<Result<Vec<Expr>, PipelineError>>() {
+                    Ok(x) => x,
+                    Err(e) => return Err(e),
+                };
+                let final_lf = pl_exprs_vec.iter().fold(lf.clone(), |acc_lf, expr| acc_lf.filter(expr.clone()) );
+                Ok(final_lf)
+            },
+            PipeConfig::Join(config) => {
+                let left_lf = match self.get_upstream_lazy_frame(&config.left_pipe_id, &["leftPipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let right_lf = match self.get_upstream_lazy_frame(&config.right_pipe_id, &["rightPipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                if let Some(e) = join_validation_error(config) {
+                    return Err(e)
+                }
+                let how = match config.how {
+                    JoinPipeType::Left | JoinPipeType::Right => JoinType::Left,
+                    JoinPipeType::Inner => JoinType::Inner,
+                    JoinPipeType::Outer => JoinType::Outer,
+                    JoinPipeType::Semi => JoinType::Semi,
+                    JoinPipeType::Anti => JoinType::Anti,
+                    JoinPipeType::Cross => return cross_join(left_lf, right_lf, config),
+                    JoinPipeType::AsOf => return asof_join(left_lf, right_lf, config),
+                };
+                let (left_on, right_on) = match join_keys(config) {
+                    Ok(x) => x,
+                    Err(e) => return Err(e),
+                };
+                let (left_lf, right_lf) = match &config.validate {
+                    Some(validation) => {
+                        let (left_lf, right_lf) = (collect_for_reuse(left_lf)?, collect_for_reuse(right_lf)?);
+                        if let JoinValidation::OneToOne | JoinValidation::OneToMany = validation {
+                            check_unique_keys(&left_lf, &left_on, "left", validation)?;
+                        }
+                        if let JoinValidation::OneToOne | JoinValidation::ManyToOne = validation {
+                            check_unique_keys(&right_lf, &right_on, "right", validation)?;
+                        }
+                        (left_lf, right_lf)
+                    },
+                    None => (left_lf, right_lf),
+                };
+                // Right joins are left joins with the pipes swapped
+                let (base_lf, other_lf, base_on, other_on) = match config.how {
+                    JoinPipeType::Right => (right_lf, left_lf, right_on, left_on),
+                    _ => (left_lf, right_lf, left_on, right_on),
+                };
+                let mut jb: JoinBuilder = JoinBuilder::new(base_lf).with(other_lf).how(how)
+                    .left_on(base_on.iter().map(|x| col(x)).collect::<Vec<_>>())
+                    .right_on(other_on.iter().map(|x| col(x)).collect::<Vec<_>>());
+                if let Some(suffix) = &config.suffix {
+                    jb = jb.suffix(suffix);
+                }
+                Ok(jb.finish())
+            },
+            PipeConfig::Rename(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                Ok(lf.rename(config.properties.iter().map(|x| x.from.clone()), config.properties.iter().map(|x| x.to.clone())))
+            },
+            PipeConfig::Sort(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                if config.by.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Must sort by at least one property").at(&["by"]))
+                }
+                Ok(sort_by_keys(lf, &config.by))
+            },
+            PipeConfig::Slice(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                Ok(lf.slice(config.offset, config.length.unwrap_or(IdxSize::MAX)))
+            },
+            PipeConfig::Select(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let columns = match resolve_columns(&*lf.schema()?, &config.properties, &config.matching) {
+                    Ok(x) => x,
+                    Err(e) => return Err(e.at(&["properties"])),
+                };
+                Ok(lf.select(columns.iter().map(|x| col(x)).collect::<Vec<_>>()))
+            },
+            PipeConfig::Drop(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let columns = match resolve_columns(&*lf.schema()?, &config.properties, &config.matching) {
+                    Ok(x) => x,
+                    Err(e) => return Err(e.at(&["properties"])),
+                };
+                Ok(lf.drop_columns(columns))
+            },
+            PipeConfig::Union(config) => {
+                if config.pipe_ids.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Union requires at least one pipe").at(&["pipeIds"]))
+                }
+                let mut lfs: Vec<LazyFrame> = Vec::new();
+                let mut schemas: Vec<Arc<Schema>> = Vec::new();
+                for (i, pipe_id) in config.pipe_ids.iter().enumerate() {
+                    let lf = match self.get_upstream_lazy_frame(pipe_id, &["pipeIds", i.to_string().as_str()]) {
+                        Ok(lf) => lf,
+                        Err(e) => return Err(e),
+                    };
+                    schemas.push(lf.schema()?);
+                    lfs.push(lf);
+                }
+                // Output columns in order of first appearance
+                let mut columns: Vec<(String, DataType)> = Vec::new();
+                for (i, schema) in schemas.iter().enumerate() {
+                    for (name, dtype) in schema.iter() {
+                        match columns.iter().find(|(x, _)| x.as_str() == name.as_str()) {
+                            None => columns.push((name.to_string(), dtype.clone())),
+                            Some((_, expected)) if expected == dtype => {},
+                            Some((_, expected)) => {
+                                let msg = format!("Column {} has dtype {} in pipe {} but {} in an earlier pipe", name, dtype, config.pipe_ids[i], expected);
+                                return Err(PipelineError::new(PipelineErrorKind::TypeMismatch, msg).at(&["pipeIds", i.to_string().as_str()]))
+                            },
+                        }
+                    }
+                }
+                let mut aligned_lfs: Vec<LazyFrame> = Vec::new();
+                for (i, (lf, schema)) in lfs.into_iter().zip(schemas.iter()).enumerate() {
+                    let missing: Vec<&str> = columns.iter().map(|(name, _)| name.as_str()).filter(|name| schema.get(name).is_none()).collect();
+                    if matches!(config.mode, UnionMode::Strict) && !missing.is_empty() {
+                        let msg = format!("Pipe {} is missing columns {}", config.pipe_ids[i], missing.join(", "));
+                        return Err(PipelineError::new(PipelineErrorKind::TypeMismatch, msg).at(&["pipeIds", i.to_string().as_str()]))
+                    }
+                    let mut exprs: Vec<Expr> = columns.iter().map(|(name, dtype)| match schema.get(name) {
+                        Some(_) => col(name),
+                        None => lit(NULL).cast(dtype.clone()).alias(name),
+                    }).collect();
+                    if let Some(source_column) = &config.source_column {
+                        if schema.get(source_column).is_some() {
+                            return Err(source_column_clash_error(source_column, &config.pipe_ids[i]))
+                        }
+                        exprs.push(lit(config.pipe_ids[i].as_str()).alias(source_column));
+                    }
+                    aligned_lfs.push(lf.select(exprs));
+                }
+                Ok(concat(aligned_lfs, true, true)?)
+            },
+            PipeConfig::Pivot(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                let pl_exprs_vec: Vec<Expr> = match config.calcs.iter().map(|calc| {
-                    match recurse_derived_expression(calc.expression.clone()) {
-                        Ok(y) => Ok(y.alias(&calc.name)),
-                        Err(e) => Err(e),
-                    }
-                }).collect::<Result<Vec<Expr>, String>>() {
+                if config.index.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Pivot requires at least one index column").at(&["index"]))
+                }
+                let key_dtype = match lf.schema()?.get(&config.columns) {
+                    Some(x) => x.clone(),
+                    None => {
+                        let msg = format!("Column {} does not exist", config.columns);
+                        return Err(PipelineError::new(PipelineErrorKind::MissingColumn, msg).at(&["columns"]))
+                    },
+                };
+                let pivot_key = col(&config.columns);
+                let (lf, pivot_values): (LazyFrame, Vec<String>) = match &config.pivot_values {
+                    Some(x) => (lf, x.clone()),
+                    None => {
+                        let lf = collect_for_reuse(lf)?;
+                        // Sorted in the column's own type, so numbers are in numeric order
+                        let distinct_key = pivot_key.clone().drop_nulls().unique().sort(false).cast(DataType::Utf8);
+                        let distinct = lf.clone().select([distinct_key]).collect()?;
+                        let values = distinct.column(&config.columns)?.utf8()?.into_iter().flatten().map(|x| x.to_string()).collect();
+                        (lf, values)
+                    },
+                };
+                if let Some((i, value)) = pivot_values.iter().enumerate().find(|(_, value)| config.index.contains(value)) {
+                    let path = match config.pivot_values {
+                        Some(_) => vec!["pivotValues".to_string(), i.to_string()],
+                        None => vec!["columns".to_string()],
+                    };
+                    return Err(pivot_index_clash_error(value).at(path.as_slice()))
+                }
+                let aggs: Vec<Expr> = match pivot_values.iter().map(|value| {
+                    // Values become column names, so are given as strings and converted to the column's type to
+                    // compare, so that e.g. "1" matches 1.0
+                    let is_value = match key_dtype {
+                        DataType::Utf8 => pivot_key.clone().eq(lit(value.as_str())),
+                        _ => pivot_key.clone().eq(lit(value.as_str()).cast(key_dtype.clone())),
+                    };
+                    let selected = col(&config.values).filter(is_value.clone());
+                    let agg = match config.agg {
+                        AggType::Sum => selected.sum(),
+                        AggType::Max => selected.max(),
+                        AggType::Min => selected.min(),
+                        AggType::List => return Err(list_agg_error().at(&["agg"])),
+                    };
+                    // Combinations with no rows are null rather than the aggregate of nothing
+                    Ok(when(is_value.sum().gt(lit(0))).then(agg).otherwise(lit(NULL)).alias(value))
+                }).collect::<Result<Vec<Expr>, PipelineError>>() {
                     Ok(x) => x,
                     Err(e) => return Err(e),
                 };
-                let final_lf = pl_exprs_vec.iter().fold(lf.clone(), |acc_lf, expr| acc_lf.with_column(expr.clone()) );
-                Ok(final_lf)
+                let index: Vec<Expr> = config.index.iter().map(|x| col(x)).collect();
+                Ok(lf.groupby_stable(index).agg(aggs))
             },
-            PipeConfig::GroupAndReduce(config) => {
-                let upstream_config = match self.pipe_configs.get(&config.pipe_id) {
-                    Some(c) => c.clone(),
-                    None => { println!("Pipe id {} not found", config.pipe_id); return Err(format!("Pipe id {} not found", config.pipe_id)) },
-                };
-                let lf = match self.recurse(&upstream_config) {
+            PipeConfig::Unpivot(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                let groupby = match config.group_by.len() {
-                    0 => { return Err("Cannot group by zero variables".into()) },
-                    1 => lf.groupby([ col(&config.group_by[0]) ]),
-                    2 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]) ]),
-                    3 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]) ]),
-                    4 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]) ]),
-                    5 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]), col(&config.group_by[4]) ]),
-                    6 => lf.groupby([ col(&config.group_by[0]), col(&config.group_by[1]), col(&config.group_by[2]), col(&config.group_by[3]), col(&config.group_by[4]), col(&config.group_by[5]) ]),
-                    _ => { return Err("Too many groupby variables. Need to implement.".into()) },
+                let schema = lf.schema()?;
+                let id_columns = match resolve_columns(&schema, &config.id_columns, &ColumnMatching::Exact) {
+                    Ok(x) => x,
+                    Err(e) => return Err(e.at(&["idColumns"])),
                 };
-                let lf_out = groupby.agg(
-                    config.clone().aggs.into_iter().map(|c| {
-                        match c.r#type {
-                            AggType::Sum => {
-                                col(&c.agg_property).sum().alias(&c.name)
-                            },
-                            AggType::Max => {
-                                col(&c.agg_property).max().alias(&c.name)
-                            },
-                            AggType::Min => {
-                                col(&c.agg_property).min().alias(&c.name)
-                            },
-                        }
-                    }).collect::<Vec<_>>()
-                );
-                Ok(lf_out)
+                let value_columns: Vec<String> = match config.value_columns.len() {
+                    0 => vec![],
+                    _ => match resolve_columns(&schema, &config.value_columns, &config.matching) {
+                        Ok(x) => x.into_iter().filter(|x| !id_columns.contains(x)).collect(),
+                        Err(e) => return Err(e.at(&["valueColumns"])),
+                    },
+                };
+                // An empty list would melt every non-id column
+                if !config.value_columns.is_empty() && value_columns.is_empty() {
+                    let msg = format!("valueColumns {} only match id columns", config.value_columns.join(", "));
+                    return Err(PipelineError::new(PipelineErrorKind::MissingColumn, msg).at(&["valueColumns"]))
+                }
+                let args = MeltArgs {
+                    id_vars: id_columns.into_iter().map(|x| x.into()).collect(),
+                    value_vars: value_columns.into_iter().map(|x| x.into()).collect(),
+                    variable_name: config.variable_name.as_ref().map(|x| x.as_str().into()),
+                    value_name: config.value_name.as_ref().map(|x| x.as_str().into()),
+                    ..Default::default()
+                };
+                Ok(lf.melt(args))
             },
-            PipeConfig::Filter(config) => {
-                let upstream_config = match self.pipe_configs.get(&config.pipe_id) {
-                    Some(c) => c.clone(),
-                    None => { println!("Pipe id {} not found", config.pipe_id); return Err(format!("Pipe id {} not found", config.pipe_id)) },
+            PipeConfig::Distinct(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
                 };
-                let lf = match self.recurse(&upstream_config) {
+                let keep = match config.keep {
+                    DistinctKeep::First => UniqueKeepStrategy::First,
+                    DistinctKeep::Last => UniqueKeepStrategy::Last,
+                    DistinctKeep::None => UniqueKeepStrategy::None,
+                };
+                match config.maintain_order {
+                    true => Ok(lf.unique_stable(config.subset.clone(), keep)),
+                    false => Ok(lf.unique(config.subset.clone(), keep)),
+                }
+            },
+            PipeConfig::Cast(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                let pl_exprs_vec: Vec<Expr> = match config.filters.iter().map(|calc| {
-                    match recurse_derived_expression(calc.clone()) {
-                        Ok(y) => Ok(y),
-                        Err(e) => Err(e),
-                    }
-                }).collect::<Result<Vec<Expr>, String>>() {
+                let cast_exprs = match build_cast_exprs(&lf.schema()?, config) {
                     Ok(x) => x,
                     Err(e) => return Err(e),
                 };
-                let final_lf = pl_exprs_vec.iter().fold(lf.clone(), |acc_lf, expr| acc_lf.filter(expr.clone()) );
-                Ok(final_lf)
+                if !config.strict && !config.count_failures {
+                    return Ok(lf.with_columns(cast_exprs))
+                }
+                let (lf, failures) = cast_counting_failures(lf, config, cast_exprs)?;
+                if config.strict {
+                    let failed: Vec<String> = failures.iter()
+                        .filter(|(_, count)| *count > 0)
+                        .map(|(column, count)| format!("{} values in column {}", count, column))
+                        .collect();
+                    if !failed.is_empty() {
+                        let msg = format!("Could not convert {}", failed.join(", "));
+                        return Err(PipelineError::new(PipelineErrorKind::TypeMismatch, msg).at(&["columns"]))
+                    }
+                }
+                if config.count_failures {
+                    self.cast_failures.borrow_mut().insert(pipe_id.clone(), failures.into_iter().collect());
+                }
+                Ok(lf)
             },
-            PipeConfig::Join(config) => {
-                let left_config = match self.pipe_configs.get(&config.left_pipe_id) {
-                    Some(c) => c,
-                    None => { println!("Could not find pipe_id {} in pipe_configs", config.left_pipe_id); return Err(format!("Could not find pipe_id {} in pipe_configs", config.left_pipe_id)) },
+            PipeConfig::StringToDate(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                let options = StrptimeOptions { format: Some(config.format.clone()), strict: true, exact: true, cache: false };
+                Ok(lf.with_column(col(&config.column_from).str().strptime(DataType::Date, options).alias(&config.column_to)))
+            },
+            PipeConfig::IntervalJoin(config) => {
+                let left_lf = match self.get_upstream_lazy_frame(&config.left_pipe_id, &["leftPipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
                 };
-                let right_config = match self.pipe_configs.get(&config.right_pipe_id) {
-                    Some(c) => c,
-                    None => { println!("Could not find pipe_id {} in pipe_configs", config.right_pipe_id); return Err(format!("Could not find pipe_id {} in pipe_configs", config.right_pipe_id)) },
+                let right_lf = match self.get_upstream_lazy_frame(&config.right_pipe_id, &["rightPipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
                 };
-                let left_lf = match self.recurse(&left_config) {
+                Ok(interval_join(left_lf, right_lf, config))
+            },
+            PipeConfig::TopNPerGroup(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                let right_lf = match self.recurse(&right_config) {
+                if config.group_by.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by zero variables").at(&["groupBy"]))
+                }
+                if config.order_by.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Must order by at least one property").at(&["orderBy"]))
+                }
+                let order_column = "__top_n_order";
+                let row_column = "__top_n_row";
+                let group: Vec<Expr> = config.group_by.iter().map(|x| col(x)).collect();
+                let ordered = sort_by_keys(lf, &config.order_by).with_row_count(order_column, None);
+                // Rows of each group are brought together, keeping their order within the group, so that row numbers
+                // within a group are consecutive and count up from its first row
+                let grouped_exprs: Vec<Expr> = group.iter().cloned().chain([col(order_column)]).collect();
+                let grouped_descending = vec![false; grouped_exprs.len()];
+                let grouped = ordered.sort_by_exprs(grouped_exprs, grouped_descending, false, true).with_row_count(row_column, None);
+                let first_in_group = col(row_column).min().over(group.clone());
+                // The position of each row in its group, where tied rows share the position of the first of them
+                let position = match config.ties {
+                    TopNTies::First => col(row_column) - first_in_group,
+                    TopNTies::WithTies => {
+                        let tied: Vec<Expr> = group.into_iter().chain(config.order_by.iter().map(|x| col(&x.property))).collect();
+                        col(row_column).min().over(tied) - first_in_group
+                    },
+                };
+                Ok(grouped.filter(position.lt(lit(config.n)))
+                    .sort(order_column, Default::default())
+                    .drop_columns([order_column, row_column]))
+            },
+            PipeConfig::Explode(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                let jb: JoinBuilder = match config.how {
-                    JoinPipeType::Left => JoinBuilder::new(left_lf).with(right_lf).how(JoinType::Left),
-                    JoinPipeType::Right => JoinBuilder::new(right_lf).with(left_lf).how(JoinType::Left),
-                    JoinPipeType::Inner => JoinBuilder::new(left_lf).with(right_lf).how(JoinType::Inner),
-                    JoinPipeType::Outer => JoinBuilder::new(left_lf).with(right_lf).how(JoinType::Outer),
-                };
-                let jb_join = match config.on.len() {
-                    1 => jb.on(&[col(&config.on[0])]),
-                    2 => jb.on(&[col(&config.on[0]), col(&config.on[1])]),
-                    3 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2])]),
-                    4 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2]), col(&config.on[3])]),
-                    5 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2]), col(&config.on[3]), col(&config.on[4])]),
-                    6 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2]), col(&config.on[3]), col(&config.on[4]), col(&config.on[5])]),
-                    7 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2]), col(&config.on[3]), col(&config.on[4]), col(&config.on[5]), col(&config.on[6])]),
-                    8 => jb.on(&[col(&config.on[0]), col(&config.on[1]), col(&config.on[2]), col(&config.on[3]), col(&config.on[4]), col(&config.on[5]), col(&config.on[6]), col(&config.on[7])]),
-                    _ => return Err(format!("Too many arguments to join on in pipe_id {:?}", config)),
-                };
-                Ok(jb_join.finish())
+                if config.properties.is_empty() {
+                    return Err(PipelineError::new(PipelineErrorKind::OperandCount, "Explode requires at least one column").at(&["properties"]))
+                }
+                Ok(lf.explode(config.properties.iter().map(|x| col(x)).collect::<Vec<_>>()))
             },
-            PipeConfig::Rename(config) => {
-                let upstream_config = match self.pipe_configs.get(&config.pipe_id) {
-                    Some(c) => c.clone(),
-                    None => { println!("Pipe id {} not found", config.pipe_id); return Err(format!("Pipe id {} not found", config.pipe_id)) },
+            PipeConfig::Sample(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
+                    Ok(lf) => lf,
+                    Err(e) => return Err(e),
+                };
+                if let Some(e) = sample_size_error(config) {
+                    return Err(e)
+                }
+                // Every column must be sampled with the same seed so that the sampled values stay in the same rows
+                let seed = config.seed.unwrap_or_else(|| Utc::now().timestamp_millis() as u64);
+                let sampled = match (config.n, config.fraction) {
+                    (Some(n), None) => col("*").sample_n(n, config.with_replacement, false, Some(seed)),
+                    (None, Some(fraction)) => col("*").sample_frac(fraction, config.with_replacement, false, Some(seed)),
+                    _ => return Err(PipelineError::new(PipelineErrorKind::InvalidInput, "Exactly one of n or fraction must be given")),
                 };
-                let lf = match self.recurse(&upstream_config) {
+                Ok(lf.select([sampled]))
+            },
+            PipeConfig::FillNull(config) => {
+                let lf = match self.get_upstream_lazy_frame(&config.pipe_id, &["pipeId"]) {
                     Ok(lf) => lf,
                     Err(e) => return Err(e),
                 };
-                Ok(lf.rename(config.properties.iter().map(|x| x.from.clone()), config.properties.iter().map(|x| x.to.clone())))
+                let schema = lf.schema()?;
+                let mut exprs = Vec::new();
+                for (i, rule) in config.rules.iter().enumerate() {
+                    match fill_null_rule_expr(rule, &schema) {
+                        Ok(x) => exprs.push(x),
+                        Err(e) => return Err(e.at(&["rules", i.to_string().as_str()])),
+                    }
+                }
+                Ok(lf.with_columns(exprs))
             },
-            // PipeConfigType::StringToDate => {
-            //     let config = from_str::<StringToDatePipeConfig>(&config_str).unwrap();
-            //     let (child_config_type, child_config) = match self.pipe_configs.get(&config.pipe_id) {
-            //         Some(v) => v,
-            //         None => { println!("Unable to find pipe_id {}", config.pipe_id); return Err(format!("Unable to find pipe_id {}", config.pipe_id)) },
-            //     };
-            //     let lf = match self.recurse(&child_config, &child_config_type) {
-            //         Ok(lf) => lf,
-            //         Err(e) => return Err(e),
-            //     };
-            //     let options = StrptimeOptions { format: Some(String::from(config.format)), strict: true, exact: true, cache: false };
-            //     return Ok(lf.with_column(col(&config.column_from).str().strptime(DataType::Date, options).alias(&config.column_to)))
-            // }
         }
     }
 }
 
 #[wasm_bindgen]
-pub fn getSourcePipes(configs: JsValue) -> Result<JsValue, String> {
+pub fn getSourcePipes(configs: JsValue) -> Result<JsValue, JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
 
     let pipe_configs: Vec<PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
         Ok(c) => c,
-        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(e.to_string()) }
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) }
     };
     let result = pipe_configs.iter().map(get_source_pipes_for_single).collect::<Vec<_>>();
     let result_jsvalue = match serde_wasm_bindgen::to_value(&result) {
         Ok(x) => x,
-        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); return Err(e.to_string()) }
+        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); return Err(PipelineError::from(e).into()) }
     };
     Ok(result_jsvalue)
 }
 
 fn get_source_pipes_for_single(config: &PipeConfig) -> Vec<String> {
+    get_source_pipe_references(config).into_iter().map(|(_, pipe_id)| pipe_id).collect()
+}
+
+/// Returns each upstream pipe id referenced by a config, along with the path of the reference within the config.
+fn get_source_pipe_references(config: &PipeConfig) -> Vec<(Vec<String>, String)> {
     match config {
-        PipeConfig::Source(c) => vec![],
-        PipeConfig::DerivedValues(c) => vec![c.pipe_id.clone()],
-        PipeConfig::GroupAndReduce(c) => vec![c.pipe_id.clone()],
-        PipeConfig::Filter(c) => vec![c.pipe_id.clone()],
-        PipeConfig::Join(c) => vec![c.left_pipe_id.clone(), c.right_pipe_id.clone()],
-        PipeConfig::Rename(c) => vec![c.pipe_id.clone()],
+        PipeConfig::Source(_) => vec![],
+        PipeConfig::DerivedValues(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::GroupAndReduce(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Filter(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Join(c) => vec![(vec!["leftPipeId".into()], c.left_pipe_id.clone()), (vec!["rightPipeId".into()], c.right_pipe_id.clone())],
+        PipeConfig::Rename(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Sort(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Slice(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Select(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Drop(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Union(c) => c.pipe_ids.iter().enumerate().map(|(i, pipe_id)| (vec!["pipeIds".into(), i.to_string()], pipe_id.clone())).collect(),
+        PipeConfig::Pivot(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Unpivot(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Distinct(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Cast(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::StringToDate(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::FillNull(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Sample(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::Explode(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::TopNPerGroup(c) => vec![(vec!["pipeId".into()], c.pipe_id.clone())],
+        PipeConfig::IntervalJoin(c) => vec![(vec!["leftPipeId".into()], c.left_pipe_id.clone()), (vec!["rightPipeId".into()], c.right_pipe_id.clone())],
     }
 }
 
 #[wasm_bindgen]
-pub fn getRootSources(configs: JsValue, endpoint: String) -> JsValue {
+pub fn getRootSources(configs: JsValue, endpoint: String) -> Result<JsValue, JsValue> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+
     let pipe_configs: HashMap<String, PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
         Ok(c) => c,
-        Err(e) => { panic!("Error parsing pipe_configs: {:?}", e) },
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) },
+    };
+    let root_sources = match get_root_sources_for_endpoint(pipe_configs, endpoint) {
+        Ok(x) => x,
+        Err(e) => { log(&e.to_string()); return Err(e.into()) },
     };
-    let result = match serde_wasm_bindgen::to_value(&get_root_sources_for_endpoint(pipe_configs, endpoint).unwrap()) {
+    let result = match serde_wasm_bindgen::to_value(&root_sources) {
         Ok(x) => x,
-        Err(e) => { panic!("Error converting result to JsValue: {:?}", e) }
+        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); return Err(PipelineError::from(e).into()) }
     };
-    result
+    Ok(result)
 }
 
-fn get_root_sources_for_endpoint(pipe_configs: HashMap<String, PipeConfig>, endpoint: String) -> Result<Vec<String>, String> {
+fn get_root_sources_for_endpoint(pipe_configs: HashMap<String, PipeConfig>, endpoint: String) -> Result<Vec<String>, PipelineError> {
+    if let Err(e) = check_for_cycles(&vec![endpoint.clone()], &pipe_configs) {
+        return Err(e)
+    }
     let mut already_traversed = HashSet::new();
     match get_root_sources_recursive(&endpoint, &pipe_configs, &mut already_traversed) {
         Ok(x) => {
@@ -666,7 +2594,7 @@ fn get_root_sources_for_endpoint(pipe_configs: HashMap<String, PipeConfig>, endp
     }
 }
 
-fn get_root_sources_recursive(pipe_id: &String, pipe_configs: &HashMap<String, PipeConfig>, already_traversed: &mut HashSet<String>) -> Result<Vec<String>, String> {
+fn get_root_sources_recursive(pipe_id: &String, pipe_configs: &HashMap<String, PipeConfig>, already_traversed: &mut HashSet<String>) -> Result<Vec<String>, PipelineError> {
     if already_traversed.contains(pipe_id) {
         return Ok(vec![])
     } else {
@@ -674,7 +2602,10 @@ fn get_root_sources_recursive(pipe_id: &String, pipe_configs: &HashMap<String, P
     }
     let config: PipeConfig = match pipe_configs.get(pipe_id) {
         Some(x) => x.clone(),
-        None => { log(&format!("No pipe config found with id {:?}", pipe_id)); return Err(format!("No pipe config found with id {:?}", pipe_id)) }
+        None => {
+            log(&format!("No pipe config found with id {:?}", pipe_id));
+            return Err(PipelineError::new(PipelineErrorKind::MissingPipe, format!("No pipe config found with id {:?}", pipe_id)).in_pipe(pipe_id))
+        }
     };
     let c = get_source_pipes_for_single(&config);
     match c.len() {
@@ -682,8 +2613,525 @@ fn get_root_sources_recursive(pipe_id: &String, pipe_configs: &HashMap<String, P
             return Ok(vec![pipe_id.clone()])
         },
         _ => {
-            let result: Vec<_> = c.iter().map(|inner_pipe_id| get_root_sources_recursive(inner_pipe_id, pipe_configs, already_traversed)).into_iter().flatten().collect();
+            let result = c.iter().map(|inner_pipe_id| get_root_sources_recursive(inner_pipe_id, pipe_configs, already_traversed)).collect::<Result<Vec<_>, PipelineError>>()?;
             return Ok(result.into_iter().flatten().collect())
         }
     }
 }
+
+#[wasm_bindgen]
+pub fn validatePipeline(configs: JsValue, endpoints: JsValue) -> Result<JsValue, JsValue> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+
+    let pipe_configs: HashMap<String, PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
+        Ok(c) => c,
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) },
+    };
+    let endpoint_ids: Vec<String> = match serde_wasm_bindgen::from_value(endpoints) {
+        Ok(c) => c,
+        Err(e) => { log(&format!("Error parsing endpoints: {:?}", e)); return Err(PipelineError::from(e).at(&["endpoints"]).into()) },
+    };
+    let diagnostics = validate_pipeline(&pipe_configs, &endpoint_ids);
+    match serde_wasm_bindgen::to_value(&diagnostics) {
+        Ok(x) => Ok(x),
+        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); Err(PipelineError::from(e).into()) }
+    }
+}
+
+/// Checks the config graph upstream of the endpoints without any data, returning every problem found rather
+/// than stopping at the first.
+fn validate_pipeline(pipe_configs: &HashMap<String, PipeConfig>, endpoints: &Vec<String>) -> Vec<PipelineError> {
+    let mut diagnostics = Vec::new();
+    let mut pipe_ids: HashSet<String> = HashSet::new();
+    for endpoint in endpoints.iter() {
+        if !pipe_configs.contains_key(endpoint) {
+            diagnostics.push(PipelineError::new(PipelineErrorKind::MissingPipe, format!("Endpoint pipe id {} not found", endpoint)).in_pipe(endpoint));
+            continue
+        }
+        pipe_ids.extend(get_upstream_pipes(endpoint, pipe_configs).into_iter().filter(|x| pipe_configs.contains_key(x)));
+    }
+    let mut sorted_pipe_ids: Vec<String> = pipe_ids.into_iter().collect();
+    sorted_pipe_ids.sort();
+
+    for pipe_id in sorted_pipe_ids.iter() {
+        let config = &pipe_configs[pipe_id];
+        for (path, upstream_id) in get_source_pipe_references(config) {
+            if !pipe_configs.contains_key(&upstream_id) {
+                let msg = format!("Pipe id {} not found", upstream_id);
+                diagnostics.push(PipelineError::new(PipelineErrorKind::MissingPipe, msg).at(path.as_slice()).in_pipe(pipe_id));
+            }
+        }
+        diagnostics.extend(validate_pipe_config(config, pipe_configs).into_iter().map(|e| e.in_pipe(pipe_id)));
+    }
+    diagnostics.extend(find_cycles(&sorted_pipe_ids, pipe_configs).iter().map(cycle_error));
+    diagnostics
+}
+
+fn pivot_index_clash_error(value: &String) -> PipelineError {
+    let msg = format!("Pivot value {} is also an index column", value);
+    PipelineError::new(PipelineErrorKind::DuplicateName, msg)
+}
+
+fn source_column_clash_error(source_column: &String, pipe_id: &String) -> PipelineError {
+    let msg = format!("Source column {} is already a column of pipe {}", source_column, pipe_id);
+    PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&["sourceColumn"])
+}
+
+/// Returns the columns a pipe outputs whatever its input, so that clashes with them can be found without data.
+fn known_output_columns(config: &PipeConfig) -> Vec<String> {
+    match config {
+        PipeConfig::DerivedValues(c) => c.calcs.iter().map(|x| x.name.clone()).collect(),
+        PipeConfig::GroupAndReduce(c) => c.group_by.iter().cloned().chain(c.aggs.iter().map(|x| x.name.clone())).collect(),
+        PipeConfig::Rename(c) => c.properties.iter().map(|x| x.to.clone()).collect(),
+        PipeConfig::Select(c) if matches!(c.matching, ColumnMatching::Exact) => c.properties.clone(),
+        PipeConfig::Union(c) => c.source_column.iter().cloned().collect(),
+        PipeConfig::Pivot(c) => c.index.iter().cloned().chain(c.pivot_values.iter().flatten().cloned()).collect(),
+        PipeConfig::Unpivot(c) => {
+            let variable = c.variable_name.clone().unwrap_or("variable".into());
+            let value = c.value_name.clone().unwrap_or("value".into());
+            c.id_columns.iter().cloned().chain([variable, value]).collect()
+        },
+        PipeConfig::StringToDate(c) => vec![c.column_to.clone()],
+        _ => vec![],
+    }
+}
+
+fn validate_pipe_config(config: &PipeConfig, pipe_configs: &HashMap<String, PipeConfig>) -> Vec<PipelineError> {
+    let mut diagnostics = Vec::new();
+    match config {
+        PipeConfig::Source(_) => {},
+        PipeConfig::DerivedValues(c) => {
+            diagnostics.extend(find_duplicate_names(c.calcs.iter().map(|x| &x.name), "calcs"));
+            for (i, calc) in c.calcs.iter().enumerate() {
+                let index = i.to_string();
+                diagnostics.extend(validate_derived_expression(&calc.expression).into_iter().map(|e| e.at(&["calcs", index.as_str(), "expression"])));
+            }
+        },
+        PipeConfig::GroupAndReduce(c) => {
+            match c.group_by.len() {
+                0 => diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by zero variables").at(&["groupBy"])),
+                1..=6 => {},
+                _ => diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by more than 6 variables").at(&["groupBy"])),
+            }
+            diagnostics.extend(find_duplicate_names(c.aggs.iter().map(|x| &x.name), "aggs"));
+        },
+        PipeConfig::Filter(c) => {
+            for (i, filter) in c.filters.iter().enumerate() {
+                let index = i.to_string();
+                diagnostics.extend(validate_derived_expression(filter).into_iter().map(|e| e.at(&["filters", index.as_str()])));
+            }
+        },
+        PipeConfig::Join(c) => {
+            diagnostics.extend(join_validation_error(c));
+            match c.how {
+                JoinPipeType::Cross => diagnostics.extend(cross_join_keys_error(c)),
+                JoinPipeType::AsOf => match join_keys(c) {
+                    Ok((left_on, _)) if left_on.len() != 1 => diagnostics.push(asof_key_count_error(c, left_on.len())),
+                    Ok(_) => {},
+                    Err(e) => diagnostics.push(e),
+                },
+                _ => diagnostics.extend(join_keys(c).err()),
+            }
+        },
+        PipeConfig::Rename(c) => {
+            for (i, name) in find_duplicates(c.properties.iter().map(|x| &x.to)) {
+                let msg = format!("Multiple properties are renamed to {}", name);
+                diagnostics.push(PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&["properties", i.to_string().as_str(), "to"]));
+            }
+        },
+        PipeConfig::Sort(c) => {
+            if c.by.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Must sort by at least one property").at(&["by"]));
+            }
+        },
+        PipeConfig::Slice(_) => {},
+        PipeConfig::Select(c) => diagnostics.extend(validate_column_patterns(&c.properties, &c.matching, "properties")),
+        PipeConfig::Drop(c) => diagnostics.extend(validate_column_patterns(&c.properties, &c.matching, "properties")),
+        PipeConfig::Union(c) => {
+            if c.pipe_ids.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Union requires at least one pipe").at(&["pipeIds"]));
+            }
+            if let Some(source_column) = &c.source_column {
+                let clash = c.pipe_ids.iter()
+                    .find(|pipe_id| pipe_configs.get(*pipe_id).map_or(false, |x| known_output_columns(x).contains(source_column)));
+                diagnostics.extend(clash.map(|pipe_id| source_column_clash_error(source_column, pipe_id)));
+            }
+        },
+        PipeConfig::Pivot(c) => {
+            if c.index.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Pivot requires at least one index column").at(&["index"]));
+            }
+            if let AggType::List = c.agg {
+                diagnostics.push(list_agg_error().at(&["agg"]));
+            }
+            if let Some(pivot_values) = &c.pivot_values {
+                for (i, value) in find_duplicates(pivot_values.iter()) {
+                    let msg = format!("Pivot value {} is given more than once", value);
+                    diagnostics.push(PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&["pivotValues", i.to_string().as_str()]));
+                }
+                for (i, value) in pivot_values.iter().enumerate().filter(|(_, value)| c.index.contains(value)) {
+                    diagnostics.push(pivot_index_clash_error(value).at(&["pivotValues", i.to_string().as_str()]));
+                }
+            }
+        },
+        PipeConfig::Unpivot(c) => {
+            diagnostics.extend(validate_column_patterns(&c.value_columns, &c.matching, "valueColumns"));
+        },
+        PipeConfig::Distinct(c) => {
+            if let Some(subset) = &c.subset {
+                if subset.is_empty() {
+                    diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Subset must contain at least one column if given").at(&["subset"]));
+                }
+            }
+        },
+        PipeConfig::Cast(c) => {
+            for (i, property) in find_duplicates(c.columns.iter().map(|x| &x.property)) {
+                let msg = format!("Column {} is converted more than once", property);
+                diagnostics.push(PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&["columns", i.to_string().as_str(), "property"]));
+            }
+        },
+        PipeConfig::StringToDate(_) => {},
+        PipeConfig::FillNull(c) => {
+            for (i, property) in find_duplicates(c.rules.iter().map(|x| &x.property)) {
+                let msg = format!("Column {} has more than one rule", property);
+                diagnostics.push(PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&["rules", i.to_string().as_str(), "property"]));
+            }
+            for (i, rule) in c.rules.iter().enumerate() {
+                let missing = match rule.strategy {
+                    FillNullStrategy::Literal if rule.value.is_none() => Some(("value", "Literal strategy requires a value")),
+                    FillNullStrategy::Column if rule.column.is_none() => Some(("column", "Column strategy requires a column")),
+                    _ => None,
+                };
+                if let Some((field, msg)) = missing {
+                    diagnostics.push(PipelineError::new(PipelineErrorKind::InvalidInput, msg).at(&["rules", i.to_string().as_str(), field]));
+                }
+            }
+        },
+        PipeConfig::Sample(c) => {
+            diagnostics.extend(sample_size_error(c));
+        },
+        PipeConfig::Explode(c) => {
+            if c.properties.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Explode requires at least one column").at(&["properties"]));
+            }
+        },
+        PipeConfig::TopNPerGroup(c) => {
+            if c.group_by.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Cannot group by zero variables").at(&["groupBy"]));
+            }
+            if c.order_by.is_empty() {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Must order by at least one property").at(&["orderBy"]));
+            }
+        },
+        PipeConfig::IntervalJoin(_) => {},
+    }
+    diagnostics
+}
+
+fn validate_derived_expression(expression: &DerivedValuesExpression) -> Vec<PipelineError> {
+    let mut diagnostics = Vec::new();
+    match expression {
+        DerivedValuesExpression::Expression(e) => {
+            if let Err(err) = check_operand_count(&e.operation, e.operands.len()) {
+                diagnostics.push(err);
+            }
+            for (i, operand) in e.operands.iter().enumerate() {
+                let index = i.to_string();
+                diagnostics.extend(validate_derived_expression(operand).into_iter().map(|err| err.at(&["operands", index.as_str()])));
+            }
+        },
+        DerivedValuesExpression::WindowAggExpression(e) => {
+            if e.over.len() > 1 {
+                diagnostics.push(PipelineError::new(PipelineErrorKind::OperandCount, "Window expression over more than one variable not supported yet").at(&["over"]));
+            }
+            if let AggType::List = e.operation {
+                diagnostics.push(list_agg_error().at(&["operation"]));
+            }
+            diagnostics.extend(validate_derived_expression(&e.operand).into_iter().map(|err| err.at(&["operand"])));
+        },
+        DerivedValuesExpression::Variable(_) | DerivedValuesExpression::Literal(_) => {},
+    }
+    diagnostics
+}
+
+/// Returns a diagnostic for each output name in `field` which repeats an earlier name.
+fn find_duplicate_names<'a>(names: impl Iterator<Item = &'a String>, field: &str) -> Vec<PipelineError> {
+    find_duplicates(names).into_iter().map(|(i, name)| {
+        let msg = format!("Output name {} is used more than once", name);
+        PipelineError::new(PipelineErrorKind::DuplicateName, msg).at(&[field, i.to_string().as_str(), "name"])
+    }).collect()
+}
+
+/// Returns the index and value of every item which repeats an earlier item.
+fn find_duplicates<'a>(values: impl Iterator<Item = &'a String>) -> Vec<(usize, &'a String)> {
+    let mut seen = HashSet::new();
+    values.enumerate().filter(|(_, value)| !seen.insert(*value)).collect()
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PipeSchema {
+    /// Output columns of the pipe, or `None` if the schema could not be resolved
+    schema: Option<TableSchema>,
+    /// Columns referenced by the pipe or any pipe upstream of it which do not exist in that pipe's input
+    missing_columns: Vec<PipelineError>,
+    /// Pipes, including this one and any upstream of it, whose output columns depend on the data. If there are
+    /// any, `schema` is `None`.
+    data_dependent: Vec<PipelineError>,
+}
+
+/// Returns an error if the output columns of a pipe depend on its data rather than only its input columns.
+fn data_dependent_schema_error(config: &PipeConfig) -> Option<PipelineError> {
+    match config {
+        PipeConfig::Pivot(c) if c.pivot_values.is_none() => {
+            let msg = "Output columns depend on the data, as no pivotValues are given";
+            Some(PipelineError::new(PipelineErrorKind::DataDependentSchema, msg).at(&["pivotValues"]))
+        },
+        _ => None,
+    }
+}
+
+#[wasm_bindgen]
+pub fn getPipeSchema(configs: JsValue, input_schemas: JsValue, pipe_id: String) -> Result<JsValue, JsValue> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+
+    let pipe_configs: HashMap<String, PipeConfig> = match serde_wasm_bindgen::from_value(configs) {
+        Ok(c) => c,
+        Err(e) => { log(&format!("Error parsing pipe_configs: {:?}", e)); return Err(PipelineError::from(e).at(&["configs"]).into()) },
+    };
+    let schemas: HashMap<String, TableSchema> = match serde_wasm_bindgen::from_value(input_schemas) {
+        Ok(c) => c,
+        Err(e) => { log(&format!("Error parsing input_schemas: {:?}", e)); return Err(PipelineError::from(e).at(&["inputSchemas"]).into()) },
+    };
+    let result = match get_pipe_schema(pipe_configs, schemas, &pipe_id) {
+        Ok(x) => x,
+        Err(e) => { log(&e.to_string()); return Err(e.into()) },
+    };
+    match serde_wasm_bindgen::to_value(&result) {
+        Ok(x) => Ok(x),
+        Err(e) => { log(&format!("Error converting result to JsValue: {:?}", e)); Err(PipelineError::from(e).into()) }
+    }
+}
+
+/// Resolves the output schema of a pipe from the schemas of its sources, using the lazy plan's schema
+/// resolution on empty frames so no data is needed.
+fn get_pipe_schema(pipe_configs: HashMap<String, PipeConfig>, input_schemas: HashMap<String, TableSchema>, pipe_id: &String) -> Result<PipeSchema, PipelineError> {
+    if let Err(e) = check_for_cycles(&vec![pipe_id.clone()], &pipe_configs) {
+        return Err(e)
+    }
+    let mut lazy_inputs: HashMap<String, LazyFrame> = HashMap::new();
+    for (key, value) in input_schemas.iter() {
+        let lf = match table_schema_to_frame(value) {
+            Ok(x) => x,
+            Err(e) => return Err(e.at(&["inputSchemas", key.as_str()])),
+        };
+        lazy_inputs.insert(key.to_string(), lf);
+    }
+    let mut upstream_ids: Vec<String> = get_upstream_pipes(pipe_id, &pipe_configs).into_iter().collect();
+    upstream_ids.sort();
+    let data_dependent: Vec<PipelineError> = upstream_ids.iter()
+        .filter_map(|id| pipe_configs.get(id).and_then(data_dependent_schema_error).map(|e| e.in_pipe(id)))
+        .collect();
+    let data_dependent_pivots: HashSet<&String> = data_dependent.iter().filter_map(|e| e.pipe_id.as_ref()).collect();
+    // Pipes downstream of a data dependent pipe are data dependent too
+    let data_dependent_ids: HashSet<String> = upstream_ids.iter()
+        .filter(|id| get_upstream_pipes(id, &pipe_configs).iter().any(|x| data_dependent_pivots.contains(x)))
+        .cloned()
+        .collect();
+    let lff = LazyFrameFactory::new(pipe_configs, lazy_inputs, HashMap::new());
+
+    let mut missing_columns = Vec::new();
+    for current_id in upstream_ids.iter() {
+        let config = match lff.pipe_configs.get(current_id) {
+            Some(c) => c,
+            None => continue,
+        };
+        let mut resolved_schemas: HashMap<String, Option<Arc<Schema>>> = HashMap::new();
+        for (path, column, input_id) in get_referenced_columns(config) {
+            let input_schema = resolved_schemas.entry(input_id.clone()).or_insert_with(|| {
+                // Inputs which fail to resolve report their own problems, and the columns of data dependent
+                // inputs aren't known
+                if data_dependent_ids.contains(&input_id) {
+                    return None
+                }
+                lff.get_lazy_frame(&input_id).ok().and_then(|lf| lf.schema().ok())
+            });
+            if let Some(schema) = input_schema {
+                if schema.get(&column).is_none() {
+                    let msg = format!("Column {} does not exist in the output of pipe {}", column, input_id);
+                    missing_columns.push(PipelineError::new(PipelineErrorKind::MissingColumn, msg).at(path.as_slice()).in_pipe(current_id));
+                }
+            }
+        }
+    }
+
+    if !data_dependent.is_empty() {
+        return Ok(PipeSchema { schema: None, missing_columns, data_dependent })
+    }
+    let schema = match lff.get_lazy_frame(pipe_id).and_then(|lf| Ok(lf.schema()?)) {
+        Ok(x) => Some(schema_to_table_schema(&x).map_err(|e| e.in_pipe(pipe_id))?),
+        Err(_) if !missing_columns.is_empty() => None,
+        Err(e) => return Err(e.in_pipe(pipe_id)),
+    };
+    Ok(PipeSchema { schema, missing_columns, data_dependent })
+}
+
+/// Returns each column referenced by a config, as the path of the reference within the config, the column
+/// name, and the id of the upstream pipe the column must exist in.
+fn get_referenced_columns(config: &PipeConfig) -> Vec<(Vec<String>, String, String)> {
+    let indexed = |field: &str, i: usize| vec![field.to_string(), i.to_string()];
+    match config {
+        PipeConfig::Source(_) => vec![],
+        PipeConfig::DerivedValues(c) => {
+            let mut columns = Vec::new();
+            for (i, calc) in c.calcs.iter().enumerate() {
+                // Calcs are applied in order, so may use the output of earlier calcs
+                let earlier_names: HashSet<&String> = c.calcs[..i].iter().map(|x| &x.name).collect();
+                for (path, column) in get_expression_columns(&calc.expression) {
+                    if !earlier_names.contains(&column) {
+                        let full_path = [indexed("calcs", i), vec!["expression".into()], path].concat();
+                        columns.push((full_path, column, c.pipe_id.clone()));
+                    }
+                }
+            }
+            columns
+        },
+        PipeConfig::GroupAndReduce(c) => {
+            let group_by_columns = c.group_by.iter().enumerate()
+                .map(|(i, column)| (indexed("groupBy", i), column.clone(), c.pipe_id.clone()));
+            let agg_columns = c.aggs.iter().enumerate()
+                .map(|(i, agg)| ([indexed("aggs", i), vec!["aggProperty".into()]].concat(), agg.agg_property.clone(), c.pipe_id.clone()));
+            group_by_columns.chain(agg_columns).collect()
+        },
+        PipeConfig::Filter(c) => {
+            c.filters.iter().enumerate()
+                .flat_map(|(i, filter)| get_expression_columns(filter).into_iter().map(move |(path, column)| ([indexed("filters", i), path].concat(), column)))
+                .map(|(path, column)| (path, column, c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::Join(c) => {
+            let on_columns = c.on.iter().enumerate()
+                .flat_map(|(i, column)| vec![
+                    (indexed("on", i), column.clone(), c.left_pipe_id.clone()),
+                    (indexed("on", i), column.clone(), c.right_pipe_id.clone()),
+                ]);
+            let left_columns = c.left_on.iter().flatten().enumerate()
+                .map(|(i, column)| (indexed("leftOn", i), column.clone(), c.left_pipe_id.clone()));
+            let right_columns = c.right_on.iter().flatten().enumerate()
+                .map(|(i, column)| (indexed("rightOn", i), column.clone(), c.right_pipe_id.clone()));
+            let by_columns = c.by.iter().enumerate()
+                .flat_map(|(i, column)| vec![
+                    (indexed("by", i), column.clone(), c.left_pipe_id.clone()),
+                    (indexed("by", i), column.clone(), c.right_pipe_id.clone()),
+                ]);
+            on_columns.chain(left_columns).chain(right_columns).chain(by_columns).collect()
+        },
+        PipeConfig::Rename(c) => {
+            c.properties.iter().enumerate()
+                .map(|(i, property)| ([indexed("properties", i), vec!["from".into()]].concat(), property.from.clone(), c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::Sort(c) => {
+            c.by.iter().enumerate()
+                .map(|(i, key)| ([indexed("by", i), vec!["property".into()]].concat(), key.property.clone(), c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::Slice(_) => vec![],
+        PipeConfig::Select(SelectPipeConfig { pipe_id, properties, matching: ColumnMatching::Exact })
+        | PipeConfig::Drop(DropPipeConfig { pipe_id, properties, matching: ColumnMatching::Exact }) => {
+            properties.iter().enumerate()
+                .map(|(i, property)| (indexed("properties", i), property.clone(), pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::Select(_) | PipeConfig::Drop(_) => vec![],
+        PipeConfig::Union(_) => vec![],
+        PipeConfig::Pivot(c) => {
+            let index_columns = c.index.iter().enumerate()
+                .map(|(i, column)| (indexed("index", i), column.clone(), c.pipe_id.clone()));
+            let other_columns = vec![
+                (vec!["columns".to_string()], c.columns.clone(), c.pipe_id.clone()),
+                (vec!["values".to_string()], c.values.clone(), c.pipe_id.clone()),
+            ];
+            index_columns.chain(other_columns).collect()
+        },
+        PipeConfig::Unpivot(c) => {
+            let id_columns = c.id_columns.iter().enumerate()
+                .map(|(i, column)| (indexed("idColumns", i), column.clone(), c.pipe_id.clone()));
+            let value_columns: Vec<_> = match c.matching {
+                ColumnMatching::Exact => c.value_columns.iter().enumerate()
+                    .map(|(i, column)| (indexed("valueColumns", i), column.clone(), c.pipe_id.clone()))
+                    .collect(),
+                _ => vec![],
+            };
+            id_columns.chain(value_columns).collect()
+        },
+        PipeConfig::Distinct(c) => {
+            c.subset.iter().flatten().enumerate()
+                .map(|(i, column)| (indexed("subset", i), column.clone(), c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::Cast(c) => {
+            c.columns.iter().enumerate()
+                .map(|(i, column)| ([indexed("columns", i), vec!["property".to_string()]].concat(), column.property.clone(), c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::StringToDate(c) => vec![(vec!["columnFrom".into()], c.column_from.clone(), c.pipe_id.clone())],
+        PipeConfig::FillNull(c) => {
+            c.rules.iter().enumerate().flat_map(|(i, rule)| {
+                let path = |field: &str| [indexed("rules", i), vec![field.to_string()]].concat();
+                let mut columns = vec![(path("property"), rule.property.clone(), c.pipe_id.clone())];
+                if let Some(column) = &rule.column {
+                    columns.push((path("column"), column.clone(), c.pipe_id.clone()));
+                }
+                columns.extend(rule.over.iter().enumerate().map(|(j, column)| ([path("over"), vec![j.to_string()]].concat(), column.clone(), c.pipe_id.clone())));
+                columns
+            }).collect()
+        },
+        PipeConfig::Sample(_) => vec![],
+        PipeConfig::Explode(c) => {
+            c.properties.iter().enumerate()
+                .map(|(i, column)| (indexed("properties", i), column.clone(), c.pipe_id.clone()))
+                .collect()
+        },
+        PipeConfig::TopNPerGroup(c) => {
+            let group_columns = c.group_by.iter().enumerate()
+                .map(|(i, column)| (indexed("groupBy", i), column.clone(), c.pipe_id.clone()));
+            let order_columns = c.order_by.iter().enumerate()
+                .map(|(i, key)| ([indexed("orderBy", i), vec!["property".into()]].concat(), key.property.clone(), c.pipe_id.clone()));
+            group_columns.chain(order_columns).collect()
+        },
+        PipeConfig::IntervalJoin(c) => {
+            let bound_columns = vec![
+                (vec!["value".to_string()], c.value.clone(), c.left_pipe_id.clone()),
+                (vec!["start".to_string()], c.start.clone(), c.right_pipe_id.clone()),
+                (vec!["end".to_string()], c.end.clone(), c.right_pipe_id.clone()),
+            ];
+            let by_columns = c.by.iter().enumerate()
+                .flat_map(|(i, column)| vec![
+                    (indexed("by", i), column.clone(), c.left_pipe_id.clone()),
+                    (indexed("by", i), column.clone(), c.right_pipe_id.clone()),
+                ]);
+            bound_columns.into_iter().chain(by_columns).collect()
+        },
+    }
+}
+
+/// Returns each column used in an expression, along with the path of the reference within the expression.
+fn get_expression_columns(expression: &DerivedValuesExpression) -> Vec<(Vec<String>, String)> {
+    match expression {
+        DerivedValuesExpression::Expression(e) => {
+            e.operands.iter().enumerate()
+                .flat_map(|(i, operand)| get_expression_columns(operand).into_iter().map(move |(path, column)| ([vec!["operands".to_string(), i.to_string()], path].concat(), column)))
+                .collect()
+        },
+        DerivedValuesExpression::WindowAggExpression(e) => {
+            let operand_columns = get_expression_columns(&e.operand).into_iter()
+                .map(|(path, column)| ([vec!["operand".to_string()], path].concat(), column));
+            let over_columns = e.over.iter().enumerate()
+                .map(|(i, column)| (vec!["over".to_string(), i.to_string()], column.clone()));
+            operand_columns.chain(over_columns).collect()
+        },
+        DerivedValuesExpression::Variable(v) => vec![(vec!["property".to_string()], v.property.clone())],
+        DerivedValuesExpression::Literal(_) => vec![],
+    }
+}